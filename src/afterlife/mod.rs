@@ -1 +1,3 @@
-pub mod over;
\ No newline at end of file
+pub mod replay;
+pub mod result;
+pub mod stats;
@@ -0,0 +1,958 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+    window::PrimaryWindow,
+};
+use bevy_pkv::PkvStore;
+use bevy_spritesheet_animation::prelude::*;
+use big_brain::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animations::{
+        build::AniRegistry,
+        loader::{AniHandles, AniSet},
+    },
+    brains::{
+        fight::Fighter,
+        loot::{Looted, Looter},
+        order::Orders,
+        patrol::Patrol,
+    },
+    characters::{
+        actions::{Act, Action},
+        builder::{init_characters, spawn_character},
+        entities::{CharacterId, CharacterKind},
+        update::update_character,
+    },
+    core::{
+        chest::{update_chest, Chest, ChestId, ChestState, Chests},
+        gate::{open_entrance_gate, update_gate, GateId, GateState, Gates, Opened},
+        grave::{update_sealed_grave, Grave, Sealed, SpawnedBy, Spawner},
+        layer::{y_sort, SpriteLayer},
+        lever::{init_switches, update_lever, Lever, Switched},
+        map::{convert_screen_to_map, get_map_from_position},
+        position::Position,
+        scene::ChunkMap,
+        seed::{RunSeed, StageSeed},
+        setup::setup_scene,
+        stage::{init_stage, GameStage},
+        state::GameState,
+    },
+    entry::{despawn_screen, game::OnGameScreen, TEXT_COLOR},
+    interactions::{
+        damage::{despawn_damage_indicator, spawn_damage_indicator, Damage, DamageEvent},
+        toggle::{Interactable, ToggleEvent},
+    },
+};
+
+// Seconds between two recorded ticks.
+pub const RECORD_STEP: f32 = 0.1;
+// Moves smaller than this are not worth a record.
+const MIN_MOVE: f32 = 0.5;
+
+// Kept with `PkvStore` like the save, local storage on web.
+const REPLAY_KEY: &str = "replay";
+const TIMELINE_KEY: &str = "timeline";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CharacterFrame {
+    pub id: String,
+    pub act: Act,
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DamageFrame {
+    pub by: CharacterKind,
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ToggleFrame {
+    pub by: CharacterKind,
+    pub x: f32,
+    pub y: f32,
+}
+
+// Risen from a grave, copied from the `template` enemy.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SpawnFrame {
+    pub id: String,
+    pub template: String,
+}
+
+// Only what changed since the previous tick.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Frame {
+    pub tick: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub characters: Vec<CharacterFrame>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub damages: Vec<DamageFrame>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub toggles: Vec<ToggleFrame>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spawns: Vec<SpawnFrame>,
+    // Cells of chests, gates, levers or graves opened, switched or sealed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub opened: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    pub seed: u64,
+    // Empty for recordings older than `StageSeed`
+    #[serde(default)]
+    pub public_key: String,
+    pub stage_id: String,
+    pub frames: Vec<Frame>,
+}
+
+impl Recording {
+    pub fn last_tick(&self) -> u32 {
+        self.frames.last().map(|frame| frame.tick).unwrap_or(0)
+    }
+
+    // Where everyone is at `tick`, from the latest record of each.
+    pub fn seek(&self, tick: u32) -> HashMap<String, CharacterFrame> {
+        let mut characters = HashMap::new();
+        for frame in self.frames.iter().take_while(|frame| frame.tick <= tick) {
+            for character in frame.characters.iter() {
+                characters.insert(character.id.clone(), character.clone());
+            }
+        }
+
+        characters
+    }
+
+    // Everything opened by `tick`, to put back when scrubbing either way.
+    pub fn opened_at(&self, tick: u32) -> HashSet<String> {
+        self.frames
+            .iter()
+            .take_while(|frame| frame.tick <= tick)
+            .flat_map(|frame| frame.opened.iter().cloned())
+            .collect()
+    }
+
+    pub fn find_spawn(&self, id: &str) -> Option<&SpawnFrame> {
+        self.frames
+            .iter()
+            .flat_map(|frame| frame.spawns.iter())
+            .find(|spawn| spawn.id == id)
+    }
+
+    // One row per act, `to` is where that act ended, like `assets/timeline.csv`, e.g. `man_0,walk,f1,e5`.
+    pub fn to_timeline_csv(&self) -> String {
+        // (id, act, at, to)
+        let mut rows: Vec<(String, Act, Vec2, Vec2)> = vec![];
+        let mut current: HashMap<String, usize> = HashMap::new();
+
+        for frame in self.frames.iter() {
+            for character in frame.characters.iter() {
+                let xy = Vec2::new(character.x, character.y);
+                let same_act = current
+                    .get(&character.id)
+                    .copied()
+                    .filter(|index| rows[*index].1 == character.act);
+                match same_act {
+                    Some(index) => rows[index].3 = xy,
+                    None => {
+                        current.insert(character.id.clone(), rows.len());
+                        rows.push((character.id.clone(), character.act, xy, xy));
+                    }
+                }
+            }
+        }
+
+        let mut csv = String::from("id,act,at,to\n");
+        for (id, act, at, to) in rows {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                id,
+                act,
+                get_cell(at),
+                get_cell(to)
+            ));
+        }
+
+        csv
+    }
+}
+
+fn get_cell(xy: Vec2) -> String {
+    let (x, y) = get_map_from_position(xy, None);
+    convert_screen_to_map(x, y)
+}
+
+// Character risen from a grave during the replay.
+#[derive(Component)]
+struct RisenPuppet;
+
+#[derive(Resource, Default, Debug)]
+pub struct Recorder {
+    pub recording: Recording,
+    tick: u32,
+    elapsed: f32,
+    last: HashMap<String, CharacterFrame>,
+    damages: Vec<DamageFrame>,
+    toggles: Vec<ToggleFrame>,
+    spawns: Vec<SpawnFrame>,
+    opened: HashSet<String>,
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct Playback {
+    pub tick: u32,
+    pub paused: bool,
+    elapsed: f32,
+    shown: Option<u32>,
+}
+
+#[derive(Component)]
+struct ReplayHud;
+
+#[derive(Component)]
+struct ReplayTimeline;
+
+#[derive(Component)]
+struct ReplayCursor;
+
+// Record every run, play the last one back from the result screen.
+pub fn replay_plugin(app: &mut App) {
+    app.init_resource::<Recorder>()
+        .init_resource::<Playback>()
+        .add_systems(OnEnter(GameState::Game), start_recording.after(init_stage))
+        .add_systems(FixedUpdate, record_frame.run_if(in_state(GameState::Game)))
+        .add_systems(OnExit(GameState::Game), save_recording)
+        .add_systems(
+            OnEnter(GameState::Replay),
+            (
+                load_recording,
+                setup_scene,
+                open_entrance_gate,
+                init_stage,
+                init_switches,
+                init_characters,
+                strip_brains,
+                spawn_replay_hud,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                (
+                    replay_controls,
+                    scrub_timeline,
+                    play_risen,
+                    play_interactables,
+                    play_recording,
+                    revert_interactables,
+                    (update_chest, update_gate, update_lever, update_sealed_grave),
+                )
+                    .chain(),
+                update_replay_hud,
+                update_character,
+                y_sort,
+                (spawn_damage_indicator, despawn_damage_indicator),
+            )
+                .run_if(in_state(GameState::Replay)),
+        )
+        .add_systems(OnExit(GameState::Replay), despawn_screen::<OnGameScreen>);
+}
+
+fn start_recording(
+    mut recorder: ResMut<Recorder>,
+    run_seed: Res<RunSeed>,
+    stage_seed: Res<StageSeed>,
+    game_stage: Res<GameStage>,
+) {
+    *recorder = Recorder {
+        recording: Recording {
+            seed: run_seed.0,
+            public_key: stage_seed.0.clone(),
+            stage_id: game_stage.0.id.clone(),
+            frames: vec![],
+        },
+        ..default()
+    };
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn record_frame(
+    time: Res<Time>,
+    mut recorder: ResMut<Recorder>,
+    characters: Query<(&CharacterId, &Action, &Position)>,
+    risen: Query<(&CharacterId, &SpawnedBy), Added<SpawnedBy>>,
+    spawners: Query<&Spawner>,
+    interactables: Query<
+        (
+            &Position,
+            Option<&ChestId>,
+            Option<&GateId>,
+            Option<&Switched>,
+            Option<&Sealed>,
+        ),
+        With<Interactable>,
+    >,
+    chests: Res<Chests>,
+    gates: Res<Gates>,
+    mut damage_events: EventReader<DamageEvent>,
+    mut toggle_events: EventReader<ToggleEvent>,
+) {
+    // Events come every frame, keep them for the next tick.
+    for DamageEvent(damage) in damage_events.read() {
+        recorder.damages.push(DamageFrame {
+            by: damage.by,
+            x: damage.position.x,
+            y: damage.position.y,
+            radius: damage.radius,
+        });
+    }
+    for ToggleEvent(toggle) in toggle_events.read() {
+        recorder.toggles.push(ToggleFrame {
+            by: toggle.by,
+            x: toggle.position.x,
+            y: toggle.position.y,
+        });
+    }
+    for (character_id, spawned_by) in risen.iter() {
+        if let Ok(spawner) = spawners.get(spawned_by.0) {
+            recorder.spawns.push(SpawnFrame {
+                id: character_id.0.clone(),
+                template: spawner.grave_def.template.0.clone(),
+            });
+        }
+    }
+
+    recorder.elapsed += time.delta_seconds();
+    if recorder.elapsed < RECORD_STEP {
+        return;
+    }
+    recorder.elapsed -= RECORD_STEP;
+
+    let mut frame = Frame {
+        tick: recorder.tick,
+        damages: std::mem::take(&mut recorder.damages),
+        toggles: std::mem::take(&mut recorder.toggles),
+        spawns: std::mem::take(&mut recorder.spawns),
+        ..default()
+    };
+    recorder.tick += 1;
+
+    // Never close again in a run, only the new ones
+    for (position, chest_id, gate_id, switched, sealed) in interactables.iter() {
+        let is_opened = chest_id
+            .and_then(|id| chests.0.get(&id.0))
+            .is_some_and(|chest| chest.status == ChestState::Open)
+            || gate_id
+                .and_then(|id| gates.0.get(&id.0))
+                .is_some_and(|gate| gate.status == GateState::Open)
+            || switched.is_some()
+            || sealed.is_some();
+        let cell = get_cell(position.xy);
+        if is_opened && recorder.opened.insert(cell.clone()) {
+            frame.opened.push(cell);
+        }
+    }
+
+    for (character_id, action, position) in characters.iter() {
+        let character = CharacterFrame {
+            id: character_id.0.clone(),
+            act: action.0,
+            x: position.xy.x,
+            y: position.xy.y,
+        };
+
+        let is_changed = match recorder.last.get(&character.id) {
+            Some(last) => {
+                last.act != character.act
+                    || Vec2::new(last.x, last.y).distance(position.xy) > MIN_MOVE
+            }
+            None => true,
+        };
+
+        if is_changed {
+            recorder
+                .last
+                .insert(character.id.clone(), character.clone());
+            frame.characters.push(character);
+        }
+    }
+
+    let is_empty = frame.characters.is_empty()
+        && frame.damages.is_empty()
+        && frame.toggles.is_empty()
+        && frame.spawns.is_empty()
+        && frame.opened.is_empty();
+    if !is_empty {
+        recorder.recording.frames.push(frame);
+    }
+}
+
+fn save_recording(recorder: Res<Recorder>, mut pkv: ResMut<PkvStore>) {
+    println!(
+        "📼 Recorded {} frames, seed:{}",
+        recorder.recording.frames.len(),
+        recorder.recording.seed
+    );
+
+    match serde_json::to_string(&recorder.recording) {
+        Ok(json) => {
+            if let Err(err) = pkv.set_string(REPLAY_KEY, &json) {
+                println!("📼 Can't save {}: {}", REPLAY_KEY, err);
+            }
+        }
+        Err(err) => println!("📼 Can't serialize: {}", err),
+    }
+
+    if let Err(err) = pkv.set_string(TIMELINE_KEY, &recorder.recording.to_timeline_csv()) {
+        println!("📼 Can't save {}: {}", TIMELINE_KEY, err);
+    }
+}
+
+// Same seed, same map, from memory or the last saved one.
+fn load_recording(
+    pkv: Res<PkvStore>,
+    mut recorder: ResMut<Recorder>,
+    mut playback: ResMut<Playback>,
+    mut run_seed: ResMut<RunSeed>,
+    mut stage_seed: ResMut<StageSeed>,
+) {
+    if recorder.recording.frames.is_empty() {
+        if let Some(recording) = pkv
+            .get::<String>(REPLAY_KEY)
+            .ok()
+            .and_then(|json| serde_json::from_str::<Recording>(&json).ok())
+        {
+            recorder.recording = recording;
+        }
+    }
+
+    println!(
+        "📼 Replay {} frames, seed:{}",
+        recorder.recording.frames.len(),
+        recorder.recording.seed
+    );
+    *run_seed = RunSeed(recorder.recording.seed);
+    if let Some(recorded_stage_seed) = StageSeed::new(&recorder.recording.public_key) {
+        *stage_seed = recorded_stage_seed;
+    }
+    *playback = Playback::default();
+}
+
+// Puppets only, the recording drive them.
+fn strip_brains(mut commands: Commands, characters: Query<Entity, With<CharacterId>>) {
+    for entity in characters.iter() {
+        commands
+            .entity(entity)
+            .remove::<(ThinkerBuilder, Fighter, Looter, Orders, Patrol)>();
+    }
+}
+
+fn spawn_replay_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("PixelOperator-Bold.ttf");
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(0.0),
+                    right: Val::Px(0.0),
+                    bottom: Val::Px(0.0),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            SpriteLayer::Ui,
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font,
+                        font_size: 16.0,
+                        color: TEXT_COLOR,
+                    },
+                ),
+                ReplayHud,
+            ));
+
+            // Click or drag to scrub
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Percent(100.0),
+                            height: Val::Px(8.0),
+                            ..default()
+                        },
+                        background_color: Color::BLACK.into(),
+                        ..default()
+                    },
+                    ReplayTimeline,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: TEXT_COLOR.into(),
+                            ..default()
+                        },
+                        ReplayCursor,
+                    ));
+                });
+        });
+}
+
+// Space: pause, ←/→: step, Home: restart, Esc: back to menu.
+fn replay_controls(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    recorder: Res<Recorder>,
+    mut playback: ResMut<Playback>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        playback.paused = !playback.paused;
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        playback.paused = true;
+        playback.tick = (playback.tick + 1).min(recorder.recording.last_tick());
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        playback.paused = true;
+        playback.tick = playback.tick.saturating_sub(1);
+    }
+    if keyboard_input.just_pressed(KeyCode::Home) {
+        playback.tick = 0;
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        game_state.set(GameState::Menu);
+    }
+}
+
+fn scrub_timeline(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    timelines: Query<&Interaction, With<ReplayTimeline>>,
+    recorder: Res<Recorder>,
+    mut playback: ResMut<Playback>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+
+    for interaction in timelines.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(cursor) = window.cursor_position() {
+            let ratio = (cursor.x / window.width()).clamp(0., 1.);
+            playback.tick = (ratio * recorder.recording.last_tick() as f32).round() as u32;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn play_recording(
+    time: Res<Time>,
+    recorder: Res<Recorder>,
+    mut playback: ResMut<Playback>,
+    mut characters: Query<(&CharacterId, &mut Position, &mut Action, &mut Sprite)>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    let recording = &recorder.recording;
+
+    if !playback.paused {
+        playback.elapsed += time.delta_seconds();
+        while playback.elapsed >= RECORD_STEP {
+            playback.elapsed -= RECORD_STEP;
+            if playback.tick < recording.last_tick() {
+                playback.tick += 1;
+            }
+        }
+    }
+
+    if playback.shown == Some(playback.tick) {
+        return;
+    }
+
+    // Replay hits only when playing forward, not when scrubbing.
+    let is_next = playback.shown.map(|shown| shown + 1) == Some(playback.tick);
+    playback.shown = Some(playback.tick);
+
+    let states = recording.seek(playback.tick);
+    for (character_id, mut position, mut action, mut sprite) in characters.iter_mut() {
+        let Some(state) = states.get(&character_id.0) else {
+            continue;
+        };
+
+        let xy = Vec2::new(state.x, state.y);
+        if xy.x != position.xy.x {
+            sprite.flip_x = xy.x < position.xy.x;
+        }
+        position.xy = xy;
+        if action.0 != state.act {
+            *action = Action(state.act);
+        }
+    }
+
+    if !is_next {
+        return;
+    }
+    let Some(frame) = recording
+        .frames
+        .iter()
+        .find(|frame| frame.tick == playback.tick)
+    else {
+        return;
+    };
+
+    for damage in frame.damages.iter() {
+        damage_events.send(DamageEvent(Damage {
+            by: damage.by,
+            position: Vec2::new(damage.x, damage.y),
+            radius: damage.radius,
+            duration: 0.5,
+            ..default()
+        }));
+    }
+}
+
+// Bring up the ones risen from graves by now, put them back when scrubbing earlier.
+#[allow(clippy::too_many_arguments)]
+fn play_risen(
+    mut commands: Commands,
+    recorder: Res<Recorder>,
+    playback: Res<Playback>,
+    game_stage: Res<GameStage>,
+    asset_server: Res<AssetServer>,
+    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut library: ResMut<AnimationLibrary>,
+    mut ani_registry: ResMut<AniRegistry>,
+    ani_handles: Res<AniHandles>,
+    ani_sets: Res<Assets<AniSet>>,
+    characters: Query<&CharacterId>,
+    puppets: Query<(Entity, &CharacterId), With<RisenPuppet>>,
+) {
+    // Before `play_recording` mark it shown
+    if playback.shown == Some(playback.tick) {
+        return;
+    }
+
+    let recording = &recorder.recording;
+    let states = recording.seek(playback.tick);
+
+    for (entity, character_id) in puppets.iter() {
+        if !states.contains_key(&character_id.0) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    let Some(ani_set) = ani_sets.get(&ani_handles.characters) else {
+        return;
+    };
+    for (id, state) in states.iter() {
+        if characters.iter().any(|character_id| character_id.0 == *id) {
+            continue;
+        }
+        let Some(template) = recording.find_spawn(id).and_then(|spawn| {
+            game_stage
+                .0
+                .enemies
+                .iter()
+                .find(|enemy| enemy.character_id.0 == spawn.template)
+        }) else {
+            continue;
+        };
+        let Some(ani) = ani_set
+            .0
+            .iter()
+            .find(|ani| ani.ani_type == template.ani_type)
+        else {
+            continue;
+        };
+
+        let mut character = template.clone();
+        character.character_id = CharacterId(id.clone());
+        character.act = state.act;
+        let entity = spawn_character(
+            &mut commands,
+            &asset_server,
+            &mut atlas_layouts,
+            &mut library,
+            &mut ani_registry,
+            ani,
+            &character,
+            Vec2::new(state.x, state.y),
+        );
+        commands.entity(entity).insert(RisenPuppet).remove::<(
+            ThinkerBuilder,
+            Fighter,
+            Looter,
+            Orders,
+            Patrol,
+        )>();
+    }
+}
+
+// Chests, gates, levers and graves as they were at the tick, either way.
+#[allow(clippy::type_complexity)]
+fn play_interactables(
+    mut commands: Commands,
+    recorder: Res<Recorder>,
+    playback: Res<Playback>,
+    mut chests: ResMut<Chests>,
+    mut gates: ResMut<Gates>,
+    interactables: Query<(
+        Entity,
+        &Interactable,
+        &Position,
+        Option<&ChestId>,
+        Option<&GateId>,
+    )>,
+) {
+    // Before `play_recording` mark it shown
+    if playback.shown == Some(playback.tick) {
+        return;
+    }
+
+    let opened = recorder.recording.opened_at(playback.tick);
+    for (entity, interactable, position, chest_id, gate_id) in interactables.iter() {
+        let is_opened = opened.contains(&get_cell(position.xy));
+
+        match interactable {
+            Interactable::Chest => {
+                if let Some(chest) = chest_id.and_then(|id| chests.0.get_mut(&id.0)) {
+                    chest.status = if is_opened {
+                        ChestState::Open
+                    } else {
+                        ChestState::Close
+                    };
+                }
+            }
+            Interactable::Gate => {
+                if let Some(gate) = gate_id.and_then(|id| gates.0.get_mut(&id.0)) {
+                    gate.status = if is_opened {
+                        GateState::Open
+                    } else {
+                        GateState::Close
+                    };
+                }
+            }
+            Interactable::Lever | Interactable::PressurePlate if is_opened => {
+                commands.entity(entity).insert(Switched);
+            }
+            Interactable::Lever | Interactable::PressurePlate => {
+                commands.entity(entity).remove::<Switched>();
+            }
+            Interactable::Grave if is_opened => {
+                commands.entity(entity).insert(Sealed);
+            }
+            Interactable::Grave => {
+                commands.entity(entity).remove::<Sealed>();
+            }
+        }
+    }
+}
+
+// Undo `update_chest`, `update_gate`, `update_lever` and `update_sealed_grave` when scrubbed back.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn revert_interactables(
+    mut commands: Commands,
+    ani_registry: Res<AniRegistry>,
+    chests: Res<Chests>,
+    gates: Res<Gates>,
+    mut chunk_map: ResMut<ChunkMap>,
+    mut looted: Query<
+        (Entity, &ChestId, &mut SpritesheetAnimation),
+        (With<Chest>, With<Looted>, Without<GateId>),
+    >,
+    mut opened: Query<(Entity, &GateId, &mut SpritesheetAnimation), With<Opened>>,
+    mut levers: Query<&mut Sprite, (With<Lever>, Without<Grave>)>,
+    mut graves: Query<&mut Sprite, With<Grave>>,
+    mut unswitched: RemovedComponents<Switched>,
+    mut unsealed: RemovedComponents<Sealed>,
+) {
+    for (entity, chest_id, mut animation) in looted.iter_mut() {
+        if chests.0.get(&chest_id.0).map(|chest| chest.status) == Some(ChestState::Close) {
+            if let Some(close_animation_id) = ani_registry.get("chest_close") {
+                animation.switch(close_animation_id);
+            }
+            commands.entity(entity).remove::<Looted>();
+        }
+    }
+
+    for (entity, gate_id, mut animation) in opened.iter_mut() {
+        let Some(gate) = gates.0.get(&gate_id.0) else {
+            continue;
+        };
+        if gate.status == GateState::Close {
+            if let Some(close_animation_id) = ani_registry.get("gate_close") {
+                animation.switch(close_animation_id);
+            }
+            chunk_map.walkables[gate.at.y][gate.at.x] = false;
+            commands.entity(entity).remove::<Opened>();
+        }
+    }
+
+    for entity in unswitched.read() {
+        if let Ok(mut sprite) = levers.get_mut(entity) {
+            sprite.flip_x = !sprite.flip_x;
+        }
+    }
+    for entity in unsealed.read() {
+        if let Ok(mut sprite) = graves.get_mut(entity) {
+            sprite.color = Color::WHITE;
+        }
+    }
+}
+
+fn update_replay_hud(
+    recorder: Res<Recorder>,
+    playback: Res<Playback>,
+    mut texts: Query<&mut Text, With<ReplayHud>>,
+    mut cursors: Query<&mut Style, With<ReplayCursor>>,
+) {
+    let last_tick = recorder.recording.last_tick().max(1);
+
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = format!(
+            "{} {:.1}s / {:.1}s",
+            if playback.paused { "PAUSE" } else { "PLAY" },
+            playback.tick as f32 * RECORD_STEP,
+            last_tick as f32 * RECORD_STEP
+        );
+    }
+
+    for mut style in cursors.iter_mut() {
+        style.width = Val::Percent(playback.tick as f32 / last_tick as f32 * 100.);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::map::get_position_from_map;
+
+    fn at(x: usize, y: usize) -> (f32, f32) {
+        let xy = get_position_from_map(x, y, None).translation.xy();
+        (xy.x, xy.y)
+    }
+
+    fn character(id: &str, act: Act, (x, y): (f32, f32)) -> CharacterFrame {
+        CharacterFrame {
+            id: id.to_string(),
+            act,
+            x,
+            y,
+        }
+    }
+
+    fn recording() -> Recording {
+        Recording {
+            seed: 42,
+            public_key: "gistmeAhMG7AcKSPCHis8JikGmKT9tRRyZpyMLNNULq".to_string(),
+            stage_id: "1-1".to_string(),
+            frames: vec![
+                Frame {
+                    tick: 0,
+                    characters: vec![
+                        character("man_0", Act::Idle, at(5, 1)),
+                        character("skeleton_0", Act::Idle, at(3, 4)),
+                    ],
+                    ..default()
+                },
+                Frame {
+                    tick: 10,
+                    characters: vec![character("man_0", Act::Walk, at(5, 2))],
+                    ..default()
+                },
+                Frame {
+                    tick: 20,
+                    characters: vec![character("man_0", Act::Walk, at(4, 4))],
+                    ..default()
+                },
+                Frame {
+                    tick: 30,
+                    characters: vec![character("man_0", Act::Attack, at(4, 4))],
+                    ..default()
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_seek() {
+        let recording = recording();
+        let states = recording.seek(15);
+
+        assert_eq!(states["man_0"].act, Act::Walk);
+        assert_eq!(states["skeleton_0"].act, Act::Idle);
+        assert_eq!(recording.last_tick(), 30);
+    }
+
+    #[test]
+    fn test_to_timeline_csv() {
+        let csv = recording().to_timeline_csv();
+
+        assert_eq!(
+            csv,
+            "id,act,at,to\n\
+             man_0,idle,f2,f2\n\
+             skeleton_0,idle,d5,d5\n\
+             man_0,walk,f3,e5\n\
+             man_0,attack,e5,e5\n"
+        );
+    }
+
+    #[test]
+    fn test_opened_at() {
+        let mut recording = recording();
+        recording.frames[0].opened = vec!["e1".to_string()];
+        recording.frames[2].opened = vec!["c4".to_string()];
+
+        // Scrubbed back before the chest, only the entrance
+        assert_eq!(
+            recording.opened_at(15),
+            HashSet::from_iter(["e1".to_string()])
+        );
+        assert_eq!(recording.opened_at(30).len(), 2);
+    }
+
+    #[test]
+    fn test_find_spawn() {
+        let mut recording = recording();
+        recording.frames[1].spawns = vec![SpawnFrame {
+            id: "skeleton_0_1".to_string(),
+            template: "skeleton_0".to_string(),
+        }];
+
+        assert_eq!(
+            recording
+                .find_spawn("skeleton_0_1")
+                .map(|spawn| spawn.template.as_str()),
+            Some("skeleton_0")
+        );
+        assert!(recording.find_spawn("skeleton_0").is_none());
+    }
+
+    #[test]
+    fn test_recording_roundtrip() {
+        let recording = recording();
+        let json = serde_json::to_string(&recording).unwrap();
+
+        assert_eq!(serde_json::from_str::<Recording>(&json).unwrap(), recording);
+    }
+}
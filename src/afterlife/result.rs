@@ -0,0 +1,269 @@
+use bevy::{color::palettes::css::CRIMSON, prelude::*};
+
+use crate::{
+    core::{
+        chest::{ChestState, Chests},
+        objective::ObjectiveTracker,
+        seed::RunSeed,
+        stage::{GameStage, StageId},
+        state::GameState,
+    },
+    entry::{
+        despawn_screen,
+        menu::{button_system, NORMAL_BUTTON},
+        Language, TEXT_COLOR,
+    },
+};
+
+use super::stats::RunStats;
+
+// This plugin shows the result of a run, both for `GameState::Over` and `GameState::Clear`
+pub fn result_plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameState::Over), game_over_setup)
+        .add_systems(OnExit(GameState::Over), despawn_screen::<OnResultScreen>)
+        .add_systems(OnEnter(GameState::Clear), stage_clear_setup)
+        .add_systems(OnExit(GameState::Clear), despawn_screen::<OnResultScreen>)
+        .add_systems(
+            Update,
+            (result_action, button_system)
+                .run_if(in_state(GameState::Over).or_else(in_state(GameState::Clear))),
+        );
+}
+
+// Tag component used to tag entities added on the result screen
+#[derive(Component)]
+struct OnResultScreen;
+
+// All actions that can be triggered from a button click
+#[derive(Component)]
+enum ResultButtonAction {
+    Retry,
+    Replay,
+    NextStage,
+    BackToMenu,
+}
+
+fn game_over_setup(
+    commands: Commands,
+    asset_server: Res<AssetServer>,
+    run_stats: Res<RunStats>,
+    objective_tracker: Res<ObjectiveTracker>,
+    chests: Res<Chests>,
+    language: Res<Language>,
+) {
+    result_setup(
+        commands,
+        asset_server,
+        &language,
+        "GAME OVER!",
+        &get_stats_lines(&run_stats, &objective_tracker, &chests, &language),
+        false,
+    );
+}
+
+fn stage_clear_setup(
+    commands: Commands,
+    asset_server: Res<AssetServer>,
+    run_stats: Res<RunStats>,
+    objective_tracker: Res<ObjectiveTracker>,
+    chests: Res<Chests>,
+    game_stage: Res<GameStage>,
+    language: Res<Language>,
+) {
+    println!("🏁 Stage clear:{}", game_stage.0.id);
+    result_setup(
+        commands,
+        asset_server,
+        &language,
+        "STAGE CLEAR!",
+        &get_stats_lines(&run_stats, &objective_tracker, &chests, &language),
+        game_stage.0.next.is_some(),
+    );
+}
+
+fn get_stats_lines(
+    run_stats: &RunStats,
+    objective_tracker: &ObjectiveTracker,
+    chests: &Chests,
+    language: &Language,
+) -> Vec<String> {
+    let opened_chests = chests
+        .0
+        .values()
+        .filter(|chest| chest.status == ChestState::Open)
+        .count();
+
+    vec![
+        format!(
+            "{}: {:.0}s",
+            language.translate("Time"),
+            objective_tracker.elapsed
+        ),
+        format!(
+            "{}: {:.0}",
+            language.translate("Damage dealt"),
+            run_stats.damage_dealt
+        ),
+        format!(
+            "{}: {:.0}",
+            language.translate("Damage taken"),
+            run_stats.damage_taken
+        ),
+        format!(
+            "{}: {}/{}",
+            language.translate("Chests opened"),
+            opened_chests,
+            chests.0.len()
+        ),
+    ]
+}
+
+fn result_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    language: &Language,
+    title: &'static str,
+    stats_lines: &[String],
+    has_next_stage: bool,
+) {
+    let font = asset_server.load("PixelOperator-Bold.ttf");
+
+    let button_style = Style {
+        width: Val::Px(140.0),
+        height: Val::Px(32.0),
+        margin: UiRect::all(Val::Px(4.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font: font.clone(),
+        font_size: 20.0,
+        color: TEXT_COLOR,
+    };
+    let stats_text_style = TextStyle {
+        font: font.clone(),
+        font_size: 16.0,
+        color: TEXT_COLOR,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnResultScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(8.0)),
+                        ..default()
+                    },
+                    background_color: CRIMSON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    // Display the result
+                    parent.spawn(
+                        TextBundle::from_section(
+                            language.translate(title),
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 40.0,
+                                color: TEXT_COLOR,
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(8.0)),
+                            ..default()
+                        }),
+                    );
+
+                    // Display the stats
+                    for line in stats_lines {
+                        parent.spawn(TextBundle::from_section(
+                            line.clone(),
+                            stats_text_style.clone(),
+                        ));
+                    }
+
+                    // Display a button for each action available from the result:
+                    // - retry with the same seed
+                    // - replay the run
+                    // - next stage, once cleared and when there's one
+                    // - back to menu
+                    for (action, text) in [
+                        (ResultButtonAction::Retry, "Retry"),
+                        (ResultButtonAction::Replay, "Replay"),
+                        (ResultButtonAction::NextStage, "Next Stage"),
+                        (ResultButtonAction::BackToMenu, "Menu"),
+                    ] {
+                        if matches!(action, ResultButtonAction::NextStage) && !has_next_stage {
+                            continue;
+                        }
+
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: NORMAL_BUTTON.into(),
+                                    ..default()
+                                },
+                                action,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    language.translate(text),
+                                    button_text_style.clone(),
+                                ));
+                            });
+                    }
+                });
+        });
+}
+
+fn result_action(
+    interaction_query: Query<
+        (&Interaction, &ResultButtonAction),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut run_seed: ResMut<RunSeed>,
+    mut stage_id: ResMut<StageId>,
+    game_stage: Res<GameStage>,
+) {
+    for (interaction, result_button_action) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            match result_button_action {
+                ResultButtonAction::Retry => {
+                    // Same seed, same map
+                    game_state.set(GameState::Loading);
+                }
+                ResultButtonAction::Replay => {
+                    game_state.set(GameState::Replay);
+                }
+                ResultButtonAction::NextStage => {
+                    if let Some(next) = &game_stage.0.next {
+                        *stage_id = StageId(next.clone());
+                        *run_seed = run_seed.next();
+                        game_state.set(GameState::Loading);
+                    }
+                }
+                ResultButtonAction::BackToMenu => {
+                    game_state.set(GameState::Menu);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use bevy::prelude::*;
+
+#[derive(Resource, Default, Debug, Clone)]
+pub struct RunStats {
+    pub damage_dealt: f32,
+    pub damage_taken: f32,
+}
+
+pub fn reset_run_stats(mut run_stats: ResMut<RunStats>) {
+    *run_stats = RunStats::default();
+}
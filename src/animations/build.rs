@@ -1,14 +1,43 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 use bevy_spritesheet_animation::prelude::*;
 
-use super::entities::Ani;
+use super::entities::{Ani, AniType, AnimationMode};
+
+// Animation by name, e.g. "man_idle". Names in the library can't be taken twice,
+// so we keep our own to swap in rebuilt animations on hot reload.
+// One atlas layout per type, shared by every sprite of that type.
+#[derive(Resource, Default, Debug)]
+pub struct AniRegistry {
+    pub animations: HashMap<String, AnimationId>,
+    pub layouts: HashMap<AniType, Handle<TextureAtlasLayout>>,
+}
+
+impl AniRegistry {
+    pub fn get(&self, animation_name: impl AsRef<str>) -> Option<AnimationId> {
+        self.animations.get(animation_name.as_ref()).copied()
+    }
+
+    pub fn remove_ani(&mut self, ani: &Ani) {
+        for anim in ani.animations.iter() {
+            self.animations
+                .remove(&format!("{}_{}", ani.ani_type, &anim.action_name));
+        }
+        self.layouts.remove(&ani.ani_type);
+    }
+}
 
 pub fn build_library(
     atlas_layouts: &mut ResMut<Assets<TextureAtlasLayout>>,
     library: &mut ResMut<AnimationLibrary>,
+    ani_registry: &mut ResMut<AniRegistry>,
     ani: &Ani,
     fps: u32,
-) -> Vec<(AnimationId, Handle<TextureAtlasLayout>)> {
+) -> Handle<TextureAtlasLayout> {
+    // Built once per type, until the sheet is reloaded
+    if let Some(layout) = ani_registry.layouts.get(&ani.ani_type) {
+        return layout.clone();
+    }
+
     // Create the spritesheet
     let column = ani.animations.iter().map(|e| e.count).max().unwrap_or(0);
     let spritesheet = Spritesheet::new(column, ani.animations.len());
@@ -18,32 +47,28 @@ pub fn build_library(
     let sprite_width = ani.width;
     let sprite_height = ani.height;
 
-    animations
-        .iter()
-        .map(|anim| {
-            let clip = Clip::from_frames(spritesheet.horizontal_strip(anim.x, anim.y, anim.count))
-                .with_duration(AnimationDuration::PerFrame(fps));
-            let clip_id = library.register_clip(clip);
-            let animation = Animation::from_clip(clip_id);
-            // TODO use get_animation_name
-            let animation_name = format!("{}_{}", ani.ani_type, &anim.action_name);
-
-            // Check if the animation with the same name already exists
-            if let Some(existing_animation_id) = library.animation_with_name(&animation_name) {
-                (
-                    existing_animation_id,
-                    atlas_layouts.add(spritesheet.atlas_layout(sprite_width, sprite_height)),
-                )
-            } else {
-                let animation_id = library.register_animation(animation);
-                library
-                    .name_animation(animation_id, animation_name)
-                    .unwrap();
-                (
-                    animation_id,
-                    atlas_layouts.add(spritesheet.atlas_layout(sprite_width, sprite_height)),
-                )
+    for anim in animations.iter() {
+        // Events from char.ani.json are timed on the tick, see `SwingTimings`
+        let clip = Clip::from_frames(spritesheet.horizontal_strip(anim.x, anim.y, anim.count))
+            .with_duration(AnimationDuration::PerFrame(fps));
+
+        let clip_id = library.register_clip(clip);
+        let animation = match anim.mode {
+            AnimationMode::Loop => Animation::from_clip(clip_id),
+            AnimationMode::Once => {
+                Animation::from_clip(clip_id).with_repetitions(AnimationRepeat::Times(1))
             }
-        })
-        .collect::<Vec<_>>()
+        };
+        // TODO use get_animation_name
+        let animation_name = format!("{}_{}", ani.ani_type, &anim.action_name);
+
+        let animation_id = library.register_animation(animation);
+        ani_registry.animations.insert(animation_name, animation_id);
+    }
+
+    let layout = atlas_layouts.add(spritesheet.atlas_layout(sprite_width, sprite_height));
+    ani_registry
+        .layouts
+        .insert(ani.ani_type.clone(), layout.clone());
+    layout
 }
@@ -1,13 +1,58 @@
+use std::fmt;
+
 use bevy::prelude::Component;
 use serde::Deserialize;
 use strum_macros::{Display, EnumString};
 
+#[derive(Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnimationMode {
+    #[default]
+    Loop,
+    Once,
+}
+
+// Gameplay event fired when an animation reach a frame.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, EnumString, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum AniEventKind {
+    Hit,
+    Toggle,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AniEvent {
+    pub frame: usize,
+    pub name: AniEventKind,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct AnimationDetails {
     pub action_name: String,
     pub x: usize,
     pub y: usize,
     pub count: usize,
+    #[serde(default)]
+    pub mode: AnimationMode,
+    // Same as `Hit` events
+    #[serde(default)]
+    pub hit_frames: Vec<usize>,
+    #[serde(default)]
+    pub events: Vec<AniEvent>,
+}
+
+impl AnimationDetails {
+    pub fn get_events(&self) -> Vec<AniEvent> {
+        self.hit_frames
+            .iter()
+            .map(|frame| AniEvent {
+                frame: *frame,
+                name: AniEventKind::Hit,
+            })
+            .chain(self.events.iter().cloned())
+            .collect()
+    }
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -19,16 +64,19 @@ pub struct Ani {
     pub animations: Vec<AnimationDetails>,
 }
 
-#[derive(
-    Component, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, EnumString, Display,
-)]
-#[serde(rename_all = "snake_case")]
-#[strum(serialize_all = "snake_case")]
-pub enum AniType {
-    #[default]
-    Man,
-    Skeleton,
-    Crab,
-    Gate,
-    Chest,
+// Character or decor type, e.g. "man", "skeleton" or "chest", registered from char.ani.json and decor.ani.json.
+#[derive(Component, Deserialize, Default, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct AniType(pub String);
+
+impl AniType {
+    pub fn new(name: &str) -> Self {
+        Self(name.to_owned())
+    }
+}
+
+impl fmt::Display for AniType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
@@ -0,0 +1,135 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext, LoadState},
+    prelude::*,
+    utils::HashMap,
+};
+use bevy_spritesheet_animation::prelude::*;
+
+use crate::core::state::{GameState, LoadError};
+
+use super::{
+    build::{build_library, AniRegistry},
+    entities::{Ani, AniType},
+};
+
+// All animation sheets from one `.ani.json` file, e.g. char.ani.json.
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct AniSet(pub Vec<Ani>);
+
+#[derive(Default)]
+pub struct AniLoader;
+
+impl AssetLoader for AniLoader {
+    type Asset = AniSet;
+    type Settings = ();
+    type Error = anyhow::Error;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        Ok(AniSet(serde_json::from_slice(&bytes)?))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ani.json"]
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct AniHandles {
+    pub characters: Handle<AniSet>,
+    pub decors: Handle<AniSet>,
+}
+
+impl AniHandles {
+    pub fn get<'a>(&self, ani_sets: &'a Assets<AniSet>) -> Option<(&'a AniSet, &'a AniSet)> {
+        Some((ani_sets.get(&self.characters)?, ani_sets.get(&self.decors)?))
+    }
+}
+
+pub fn load_ani_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(AniHandles {
+        characters: asset_server.load("char.ani.json"),
+        decors: asset_server.load("decor.ani.json"),
+    });
+}
+
+// Wait for the sheets before entering the game, back to the menu if one is broken.
+pub fn check_ani_assets(
+    asset_server: Res<AssetServer>,
+    ani_handles: Res<AniHandles>,
+    ani_sets: Res<Assets<AniSet>>,
+    mut load_error: ResMut<LoadError>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if ani_handles.get(&ani_sets).is_some() {
+        println!("📦 Animations loaded");
+        load_error.0 = None;
+        game_state.set(GameState::Game);
+        return;
+    }
+
+    for handle in [&ani_handles.characters, &ani_handles.decors] {
+        if let Some(LoadState::Failed(err)) = asset_server.get_load_state(handle) {
+            println!("📦 Can't load animations: {}", err);
+            load_error.0 = Some(err.to_string());
+            game_state.set(GameState::Menu);
+            return;
+        }
+    }
+}
+
+// Rebuild animations when the JSON change on disk, and swap them on what's already spawned.
+pub fn reload_ani_assets(
+    mut asset_events: EventReader<AssetEvent<AniSet>>,
+    ani_sets: Res<Assets<AniSet>>,
+    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut library: ResMut<AnimationLibrary>,
+    mut ani_registry: ResMut<AniRegistry>,
+    mut animated: Query<(&AniType, &mut TextureAtlas, &mut SpritesheetAnimation)>,
+) {
+    for event in asset_events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        let Some(ani_set) = ani_sets.get(*id) else {
+            continue;
+        };
+
+        println!("📦 Animations reloaded");
+        // Old id → name, to find the rebuilt one
+        let names = ani_registry
+            .animations
+            .iter()
+            .map(|(name, animation_id)| (*animation_id, name.clone()))
+            .collect::<HashMap<_, _>>();
+
+        let mut layouts = HashMap::new();
+        for ani in ani_set.0.iter() {
+            ani_registry.remove_ani(ani);
+            let layout =
+                build_library(&mut atlas_layouts, &mut library, &mut ani_registry, ani, 30);
+            layouts.insert(ani.ani_type.clone(), layout);
+        }
+
+        for (ani_type, mut texture_atlas, mut animation) in animated.iter_mut() {
+            let Some(layout) = layouts.get(ani_type) else {
+                continue;
+            };
+            texture_atlas.layout = layout.clone();
+
+            if let Some(animation_id) = names
+                .get(&animation.animation_id)
+                .and_then(|name| ani_registry.get(name))
+            {
+                animation.switch(animation_id);
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
 pub mod build;
 pub mod entities;
+pub mod loader;
 pub mod utils;
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use big_brain::prelude::*;
+
+use crate::{
+    characters::{
+        actions::{Act, Action},
+        bar::Morale,
+        effect::{speed_multiplier, StatusEffects},
+        entities::CharacterKind,
+    },
+    core::{faction::FactionTable, position::Position, scene::ChunkMap, stage::CharacterDef},
+    interactions::damage::apply_knockback,
+};
+
+// Below this share of morale, run for it.
+const PANIC_MORALE: f32 = 0.3;
+
+#[derive(Clone, Component, Debug, ScorerBuilder)]
+pub struct AvoidScorer;
+
+#[derive(Clone, Component, Debug, ActionBuilder)]
+pub struct Avoid {
+    speed: f32,
+}
+
+impl Avoid {
+    pub fn new(speed: f32) -> Self {
+        Self { speed }
+    }
+}
+
+// No morale, no fear.
+pub fn avoid_scorer_system(
+    morales: Query<&Morale>,
+    mut query: Query<(&Actor, &mut Score), With<AvoidScorer>>,
+) {
+    for (Actor(actor), mut score) in &mut query {
+        let ratio = morales
+            .get(*actor)
+            .map(|morale| morale.value / morale.max)
+            .unwrap_or(1.);
+
+        score.set(match ratio < PANIC_MORALE {
+            true => 1. - ratio,
+            false => 0.,
+        });
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn avoid_action_system(
+    time: Res<Time>,
+    chunk_map: Res<ChunkMap>,
+    factions: Res<FactionTable>,
+    mut characters: Query<(Entity, &mut Position, &CharacterKind)>,
+    mut actors: Query<(
+        &mut Action,
+        &mut Sprite,
+        &CharacterDef,
+        &Morale,
+        Option<&StatusEffects>,
+    )>,
+    mut action_query: Query<(&Actor, &mut ActionState, &Avoid, &ActionSpan)>,
+) {
+    for (Actor(actor), mut state, avoid, span) in &mut action_query {
+        let _guard = span.span().enter();
+
+        let Ok((mut actor_action, mut sprite, character_def, morale, effects)) =
+            actors.get_mut(*actor)
+        else {
+            continue;
+        };
+
+        match *state {
+            ActionState::Requested => {
+                println!("😱 Avoid:{:?}", character_def.character_id);
+                *state = ActionState::Executing;
+            }
+            ActionState::Executing => {
+                let Ok((_, actor_position, actor_kind)) = characters.get(*actor) else {
+                    continue;
+                };
+                let actor_xy = actor_position.xy;
+
+                // Closest foe in sight
+                let closest_foe = characters
+                    .iter()
+                    .filter(|(entity, _, kind)| {
+                        entity != actor && factions.is_hostile(actor_kind, kind)
+                    })
+                    .map(|(_, position, _)| position.xy)
+                    .filter(|xy| xy.distance(actor_xy) < character_def.line_of_sight)
+                    .min_by(|a, b| {
+                        a.distance_squared(actor_xy)
+                            .total_cmp(&b.distance_squared(actor_xy))
+                    });
+
+                // Safe, or brave again
+                let Some(closest_foe) =
+                    closest_foe.filter(|_| morale.value / morale.max < PANIC_MORALE)
+                else {
+                    *actor_action = Action(Act::Idle);
+                    *state = ActionState::Success;
+                    continue;
+                };
+
+                let Ok((_, mut actor_position, _)) = characters.get_mut(*actor) else {
+                    continue;
+                };
+
+                // Straight away from it, stay put when cornered.
+                let step = (actor_xy - closest_foe).normalize_or_zero()
+                    * avoid.speed
+                    * speed_multiplier(effects)
+                    * time.delta_seconds();
+                actor_position.xy = apply_knockback(&chunk_map.walkables, actor_xy, step);
+
+                // Look direction
+                sprite.flip_x = step.x < 0.;
+                *actor_action = Action(Act::Walk);
+            }
+            ActionState::Cancelled => {
+                debug!("😱 Avoid was interrupted.");
+                *state = ActionState::Failure;
+
+                if actor_action.0 != Act::Die {
+                    *actor_action = Action(Act::Idle);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -2,13 +2,9 @@ use bevy::prelude::*;
 use serde::Deserialize;
 use strum_macros::{Display, EnumString};
 
-use crate::{
-    get_type_id,
-    core::stage::{CharacterInfo, Human, Monster, Npc},
-    Guard,
-};
+use crate::characters::entities::CharacterKind;
 
-use std::fmt::Debug;
+use super::thinker::Guard;
 
 #[derive(Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, EnumString, Display, Reflect)]
 #[serde(rename_all = "snake_case")]
@@ -33,16 +29,10 @@ pub struct BehaviorSet {
     pub behaviors: Vec<Behavior>,
 }
 
-pub fn get_behavior<T>() -> Guard
-where
-    T: CharacterInfo + Clone + Debug + 'static,
-{
-    match get_type_id!(T) {
-        id if id == get_type_id!(Human) => Guard::new(75.0, 10.0),
-        id if id == get_type_id!(Monster) => Guard::new(75.0, 10.0),
-        id if id == get_type_id!(Npc) => {
-            todo!()
-        }
-        _ => todo!(),
+pub fn get_behavior(kind: &CharacterKind) -> Guard {
+    match kind {
+        CharacterKind::Human => Guard::new(75.0, 10.0),
+        CharacterKind::Monster => Guard::new(75.0, 10.0),
+        CharacterKind::Animal => Guard::new(50.0, 5.0),
     }
 }
@@ -1,21 +1,26 @@
 use crate::characters::actions::{Act, Action};
-use crate::characters::bar::Health;
+use crate::characters::bar::{Health, Stamina};
+use crate::characters::entities::CharacterKind;
+use crate::core::faction::FactionTable;
 use crate::core::position::Position;
-use crate::core::stage::{CharacterInfo, Human, Monster, Npc};
-use crate::interactions::damage::Death;
-use crate::{find_closest_target_with_health, get_type_id};
+use crate::core::stage::CharacterDef;
+
+use super::{
+    party::Party,
+    thinker::{find_closest_foe, MAX_DISTANCE},
+};
 use bevy::{ecs::system::EntityCommands, prelude::*};
 use big_brain::prelude::*;
-use std::fmt::Debug;
 
 #[derive(Component, Clone, Debug, Default)]
 pub struct TargetAt {
-    // TODO: use id?
-    #[allow(unused)]
-    pub id: Option<String>,
+    pub entity: Option<Entity>,
     pub last_position: Option<Position>,
 }
 
+// Swing only this close, move to the foe first otherwise.
+const FIGHT_REACH: f32 = MAX_DISTANCE * 1.5;
+
 #[derive(Clone, Component, Debug, ActionBuilder)]
 pub struct Fight {}
 
@@ -30,100 +35,85 @@ pub struct Fighter {
 pub struct FightScorer;
 
 #[allow(clippy::type_complexity)]
-pub fn fight_system<T, U>(
+pub fn fight_system(
     time: Res<Time>,
-    mut fights: Query<&mut Fighter>,
-    mut characters: Query<(&mut Position, &T), (With<T>, Without<U>)>,
-    targets: Query<(&Health, &Position), (With<U>, Without<Death>)>,
-    mut action_query: Query<&Actor>,
-) where
-    T: CharacterInfo + Clone + Debug + Component + 'static,
-    U: CharacterInfo + Clone + Debug + Component + 'static,
-{
-    match get_type_id!(T) {
-        id if id == get_type_id!(Human) || id == get_type_id!(Monster) => {
-            for Actor(actor) in &mut action_query {
-                // Use the fight_action's actor to look up the corresponding Fighter Component.
-                if let Ok(mut fighter) = fights.get_mut(*actor) {
-                    // Look up the actor's action.
-                    if let Ok((actor_position, character_info)) = characters.get_mut(*actor) {
-                        // Look up the target closest to them.
-                        match find_closest_target_with_health::<U>(&targets, &actor_position) {
-                            Some((target_health_value, closest_target)) => {
-                                if target_health_value > 0. {
-                                    // Find how far we are from it.
-                                    let delta = closest_target.xy - actor_position.xy;
-                                    let distance = delta.length();
+    factions: Res<FactionTable>,
+    party: Res<Party>,
+    mut fighters: Query<(
+        Entity,
+        &mut Fighter,
+        &Position,
+        &CharacterKind,
+        &CharacterDef,
+    )>,
+    targets: Query<(Entity, &Health, &Position, &CharacterKind)>,
+) {
+    for (actor, mut fighter, actor_position, actor_kind, character_def) in fighters.iter_mut() {
+        // Look up the foe closest to them.
+        match find_closest_foe(&targets, &factions, actor, actor_kind, actor_position) {
+            Some((target_entity, target_health_value, closest_target)) => {
+                if target_health_value > 0. {
+                    // Find how far we are from it.
+                    let delta = closest_target.xy - actor_position.xy;
+                    let distance = delta.length();
 
-                                    // Get attention when enemy getting close.
-                                    if distance < character_info.line_of_sight() {
-                                        fighter.attention +=
-                                            fighter.per_second * time.delta_seconds();
-                                        if fighter.attention >= 100.0 {
-                                            fighter.attention = 100.0;
-                                        }
-                                        trace!("Fight.attention: {}", fighter.attention);
-                                    }
-                                } else {
-                                    fighter.attention = 0.;
-                                }
-                            }
-                            None => {
-                                // TODO
-                            }
+                    // Get attention when enemy getting close, or seen by the party.
+                    let is_known = Party::is_member(actor_kind)
+                        && party.known_foes.contains_key(&target_entity);
+                    if distance < character_def.line_of_sight || is_known {
+                        fighter.attention += fighter.per_second * time.delta_seconds();
+                        if fighter.attention >= 100.0 {
+                            fighter.attention = 100.0;
                         }
+                        trace!("Fight.attention: {}", fighter.attention);
                     }
+                } else {
+                    fighter.attention = 0.;
                 }
             }
+            None => {
+                // TODO
+            }
         }
-        id if id == get_type_id!(Npc) => (),
-        _ => (),
     }
 }
 
 #[allow(clippy::type_complexity)]
-pub fn fight_scorer_system<T: Component + Debug + Clone>(
+pub fn fight_scorer_system(
     mut last_score: Local<Option<f32>>,
-    fights: Query<&Fighter>,
+    fights: Query<(&Fighter, Option<&Stamina>)>,
     mut query: Query<(&Actor, &mut Score, &ScorerSpan), With<FightScorer>>,
 ) {
-    match get_type_id!(T) {
-        id if id == get_type_id!(Human) || id == get_type_id!(Monster) => {
-            for (Actor(actor), mut score, span) in &mut query {
-                if let Ok(fighter) = fights.get(*actor) {
-                    let new_score = fighter.attention / 100.0;
+    for (Actor(actor), mut score, span) in &mut query {
+        if let Ok((fighter, stamina)) = fights.get(*actor) {
+            // Worn out fighters are less eager, down to half.
+            let eagerness = stamina.map_or(1., |stamina| 0.5 + 0.5 * stamina.value / stamina.max);
+            let new_score = fighter.attention / 100.0 * eagerness;
 
-                    if fighter.is_fighting {
-                        let _score = last_score.get_or_insert(new_score);
+            if fighter.is_fighting {
+                let _score = last_score.get_or_insert(new_score);
 
-                        score.set(*_score);
-                    } else {
-                        last_score.take();
-                        score.set(new_score);
+                score.set(*_score);
+            } else {
+                last_score.take();
+                score.set(new_score);
 
-                        if fighter.attention >= 80.0 {
-                            span.span().in_scope(|| {
-                                trace!(
-                                    "Fight above threshold! Score: {}",
-                                    fighter.attention / 100.0
-                                )
-                            });
-                        }
-                    }
+                if fighter.attention >= 80.0 {
+                    span.span().in_scope(|| {
+                        trace!(
+                            "Fight above threshold! Score: {}",
+                            fighter.attention / 100.0
+                        )
+                    });
                 }
             }
         }
-        id if id == get_type_id!(Npc) => (),
-        _ => (),
     }
 }
 
-pub fn get_fighter<T>(entity_commands: &mut EntityCommands)
-where
-    T: CharacterInfo + Clone + Debug + 'static,
-{
-    match get_type_id!(T) {
-        id if id == get_type_id!(Human) || id == get_type_id!(Monster) => {
+pub fn get_fighter(kind: &CharacterKind, entity_commands: &mut EntityCommands) {
+    match kind {
+        CharacterKind::Human | CharacterKind::Monster => {
             entity_commands.insert((
                 Fighter {
                     is_fighting: false,
@@ -133,51 +123,86 @@ where
                 FightScorer,
             ));
         }
-        id if id == get_type_id!(Npc) => (),
-        _ => (),
+        CharacterKind::Animal => (),
     }
 }
 
 #[allow(clippy::type_complexity)]
-pub fn fight_action_system<T, U>(
+pub fn fight_action_system(
+    factions: Res<FactionTable>,
+    party: Res<Party>,
     mut fights: Query<&mut Fighter>,
-    mut characters: Query<
-        (&mut TargetAt, &mut Position, &mut Action, &mut Sprite),
-        (With<T>, Without<U>),
-    >,
-    targets: Query<(&Health, &Position), (With<U>, Without<Death>)>,
+    mut characters: Query<(
+        &CharacterKind,
+        &mut TargetAt,
+        &Position,
+        &mut Action,
+        &mut Sprite,
+    )>,
+    targets: Query<(Entity, &Health, &Position, &CharacterKind)>,
     mut action_query: Query<(&Actor, &mut ActionState, &Fight, &ActionSpan)>,
-) where
-    T: CharacterInfo + Clone + Debug + 'static,
-    U: CharacterInfo + Clone + Debug + 'static,
-{
+) {
     for (Actor(actor), mut state, _fight, span) in &mut action_query {
         let _guard = span.span().enter();
 
         // Use the fight_action's actor to look up the corresponding Fighter Component.
         if let Ok(mut fighter) = fights.get_mut(*actor) {
             // Look up the actor's action.
-            if let Ok((mut actor_target_at, actor_position, mut actor_action, mut sprite)) =
-                characters.get_mut(*actor)
+            if let Ok((
+                actor_kind,
+                mut actor_target_at,
+                actor_position,
+                mut actor_action,
+                mut sprite,
+            )) = characters.get_mut(*actor)
             {
                 match *state {
                     ActionState::Requested => {
-                        debug!("🦀 Time to fight! :{}", get_type_id!(T));
+                        debug!("🦀 Time to fight! :{}", actor_kind);
                         fighter.is_fighting = true;
                         *state = ActionState::Executing;
                     }
                     ActionState::Executing => {
                         trace!("Fighting...");
 
-                        // Look up the target closest to them.
-                        match find_closest_target_with_health::<U>(&targets, &actor_position) {
-                            Some((target_health_value, closest_target)) => {
+                        // Focus fire for the party, closest foe otherwise.
+                        let foe = party
+                            .focus_for(actor_kind)
+                            .and_then(|focus| targets.get(focus).ok())
+                            .map(|(entity, health, position, _)| (entity, health.value, *position))
+                            .or_else(|| {
+                                find_closest_foe(
+                                    &targets,
+                                    &factions,
+                                    *actor,
+                                    actor_kind,
+                                    actor_position,
+                                )
+                            });
+                        match foe {
+                            Some((_, target_health_value, closest_target))
+                                if target_health_value > 0.
+                                    && closest_target.xy.distance(actor_position.xy)
+                                        > FIGHT_REACH =>
+                            {
+                                // Out of reach, e.g. the focus is elsewhere, `MoveToFoe` again
+                                actor_target_at.entity = None;
+                                actor_target_at.last_position = None;
+                                if actor_action.0 != Act::Die {
+                                    *actor_action = Action(Act::Idle);
+                                }
+
+                                fighter.is_fighting = false;
+                                *state = ActionState::Failure;
+                            }
+                            Some((target_entity, target_health_value, closest_target)) => {
                                 if target_health_value > 0. {
                                     // debug!("🦀 target_health_value:{}", target_health_value);
                                     // Look direction
                                     sprite.flip_x = actor_position.xy.x > closest_target.xy.x;
 
                                     // Lock target
+                                    actor_target_at.entity = Some(target_entity);
                                     actor_target_at.last_position = Some(closest_target);
 
                                     // Action
@@ -197,7 +222,7 @@ pub fn fight_action_system<T, U>(
                                 }
                             }
                             None => {
-                                debug!("🦀 find_closest_target_with_health NOT FOUND");
+                                debug!("🦀 find_closest_foe NOT FOUND");
                                 // Unlock target
                                 actor_target_at.last_position = None;
 
@@ -232,25 +257,3 @@ pub fn fight_action_system<T, U>(
 }
 
 // #[allow(clippy::type_complexity)]
-// pub fn death_system(
-//     mut commands: Commands,
-//     mut thinkers: Query<(&Action, Entity), With<HasThinker>>,
-//     mut action_query: Query<(&Actor, &ActionState)>,
-// ) {
-//     for (actor, _action_state) in action_query.iter_mut() {
-//         if let Some((action, thinker_entity)) =
-//             thinkers.iter().find(|(_, entity)| *entity == actor.0)
-//         {
-//             if let Ok(actor_action) = thinkers.get(thinker_entity) {
-//                 println!("action:{:?}", action.0);
-//                 match actor_action.0 .0 {
-//                     Act::Die => {
-//                         println!("===========despawn============");
-//                         commands.entity(actor.0).despawn();
-//                     }
-//                     _ => (),
-//                 }
-//             }
-//         }
-//     }
-// }
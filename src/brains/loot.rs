@@ -2,14 +2,16 @@ use crate::characters::actions::{Act, Action};
 
 use crate::core::position::Position;
 
-use crate::core::stage::{CharacterInfo, Human, Monster, Npc};
+use crate::characters::entities::CharacterKind;
+use crate::core::stage::CharacterDef;
 
-use crate::{get_type_id, find_closest_target_without_looted};
 use bevy::{ecs::system::EntityCommands, prelude::*};
 use big_brain::prelude::*;
 use std::fmt::Debug;
 
 use super::fight::TargetAt;
+use super::party::Party;
+use super::thinker::find_closest_target_without_looted;
 
 // #[derive(Component, Copy, Clone, Debug, Default)]
 // pub struct LootTargetAt {
@@ -33,93 +35,72 @@ pub struct LootScorer;
 pub struct Looted;
 
 #[allow(clippy::type_complexity)]
-pub fn loot_system<T, U>(
+pub fn loot_system<U>(
     time: Res<Time>,
-    mut loots: Query<&mut Looter>,
-    mut characters: Query<(&mut Position, &T), (With<T>, Without<U>)>,
-    targets: Query<&Position, (With<U>, Without<Looted>)>,
-    mut action_query: Query<&Actor>,
+    party: Res<Party>,
+    mut looters: Query<(Entity, &mut Looter, &Position, &CharacterDef), Without<U>>,
+    targets: Query<(Entity, &Position), (With<U>, Without<Looted>)>,
 ) where
-    T: CharacterInfo + Clone + Debug + Component + 'static,
     U: Clone + Debug + Component + 'static,
 {
-    match get_type_id!(T) {
-        id if id == get_type_id!(Human) => {
-            for Actor(actor) in &mut action_query {
-                // Use the loot_action's actor to look up the corresponding Looter Component.
-                if let Ok(mut looter) = loots.get_mut(*actor) {
-                    // Look up the actor's action.
-                    if let Ok((actor_position, character_info)) = characters.get_mut(*actor) {
-                        // Look up the target closest to them.
-                        match find_closest_target_without_looted(&targets, &actor_position) {
-                            Some(closest_target) => {
-                                // Find how far we are from it.
-                                let delta = closest_target.xy - actor_position.xy;
-                                let distance = delta.length();
-
-                                // Get attention when lootable getting close.
-                                if distance < character_info.line_of_sight() {
-                                    looter.attention += looter.per_second * time.delta_seconds();
-                                    if looter.attention >= 100.0 {
-                                        looter.attention = 100.0;
-                                    }
-
-                                    trace!("Loot.attention: {}", looter.attention);
-                                }
-                            }
-                            None => {
-                                looter.attention = 0.;
-                            }
-                        }
+    for (actor, mut looter, actor_position, character_def) in looters.iter_mut() {
+        // Look up the target closest to them.
+        match find_closest_target_without_looted(&targets, &party, actor, actor_position) {
+            Some((target_entity, closest_target)) => {
+                // Find how far we are from it.
+                let delta = closest_target.xy - actor_position.xy;
+                let distance = delta.length();
+
+                // Get attention when lootable getting close, or seen by the party.
+                let is_known = party.known_chests.contains_key(&target_entity);
+                if distance < character_def.line_of_sight || is_known {
+                    looter.attention += looter.per_second * time.delta_seconds();
+                    if looter.attention >= 100.0 {
+                        looter.attention = 100.0;
                     }
+
+                    trace!("Loot.attention: {}", looter.attention);
                 }
             }
+            None => {
+                looter.attention = 0.;
+            }
         }
-        id if id == get_type_id!(Npc) => (),
-        _ => (),
     }
 }
 
 #[allow(clippy::type_complexity)]
-pub fn loot_scorer_system<T: Component + Debug + Clone>(
+pub fn loot_scorer_system(
     mut last_score: Local<Option<f32>>,
     loots: Query<&Looter>,
-    mut query: Query<(&Actor, &mut Score, &ScorerSpan), (With<LootScorer>, Without<T>)>,
+    mut query: Query<(&Actor, &mut Score, &ScorerSpan), With<LootScorer>>,
 ) {
-    match get_type_id!(T) {
-        id if id == get_type_id!(Human) || id == get_type_id!(Monster) => {
-            for (Actor(actor), mut score, span) in &mut query {
-                if let Ok(looter) = loots.get(*actor) {
-                    let new_score = looter.attention / 100.0;
-
-                    if looter.is_looting {
-                        let _score = last_score.get_or_insert(new_score);
-
-                        score.set(*_score);
-                    } else {
-                        last_score.take();
-                        score.set(new_score);
-
-                        if looter.attention >= 80.0 {
-                            span.span().in_scope(|| {
-                                trace!("Loot above threshold! Score: {}", looter.attention / 100.0)
-                            });
-                        }
-                    }
+    for (Actor(actor), mut score, span) in &mut query {
+        if let Ok(looter) = loots.get(*actor) {
+            let new_score = looter.attention / 100.0;
+
+            if looter.is_looting {
+                let _score = last_score.get_or_insert(new_score);
+
+                score.set(*_score);
+            } else {
+                last_score.take();
+                score.set(new_score);
+
+                if looter.attention >= 80.0 {
+                    span.span().in_scope(|| {
+                        trace!("Loot above threshold! Score: {}", looter.attention / 100.0)
+                    });
                 }
             }
         }
-        id if id == get_type_id!(Npc) => (),
-        _ => (),
     }
 }
 
-pub fn get_looter<T>(entity_commands: &mut EntityCommands)
-where
-    T: CharacterInfo + Clone + Debug + 'static,
-{
-    match get_type_id!(T) {
-        id if id == get_type_id!(Human) || id == get_type_id!(Monster) => {
+// Only humans care about loot for now.
+pub fn get_looter(kind: &CharacterKind, entity_commands: &mut EntityCommands) {
+    match kind {
+        CharacterKind::Human => {
             entity_commands.insert((
                 Looter {
                     is_looting: false,
@@ -129,22 +110,18 @@ where
                 LootScorer,
             ));
         }
-        id if id == get_type_id!(Npc) => (),
-        _ => (),
+        CharacterKind::Monster | CharacterKind::Animal => (),
     }
 }
 
 #[allow(clippy::type_complexity)]
-pub fn loot_action_system<T, U>(
+pub fn loot_action_system<U>(
+    mut party: ResMut<Party>,
     mut loots: Query<&mut Looter>,
-    mut characters: Query<
-        (&mut TargetAt, &mut Position, &mut Action, &mut Sprite),
-        (With<T>, Without<U>),
-    >,
-    targets: Query<&Position, (With<U>, Without<Looted>)>,
+    mut characters: Query<(&mut TargetAt, &mut Position, &mut Action, &mut Sprite), Without<U>>,
+    targets: Query<(Entity, &Position), (With<U>, Without<Looted>)>,
     mut action_query: Query<(&Actor, &mut ActionState, &Loot, &ActionSpan)>,
 ) where
-    T: CharacterInfo + Clone + Debug + 'static,
     U: Component + Clone + Debug + 'static,
 {
     for (Actor(actor), mut state, _loot, span) in &mut action_query {
@@ -161,7 +138,7 @@ pub fn loot_action_system<T, U>(
                 println!("state:{:?}", state);
                 match *state {
                     ActionState::Requested => {
-                        debug!("🦀 Time to loot! :{}", get_type_id!(T));
+                        debug!("🦀 Time to loot!");
                         looter.is_looting = true;
                         *state = ActionState::Executing;
                     }
@@ -169,13 +146,21 @@ pub fn loot_action_system<T, U>(
                         trace!("Looting...");
 
                         // Look up the target closest to them.
-                        match find_closest_target_without_looted(&targets, &actor_position) {
-                            Some(closest_target) => {
+                        match find_closest_target_without_looted(
+                            &targets,
+                            &party,
+                            *actor,
+                            &actor_position,
+                        ) {
+                            Some((target_entity, closest_target)) => {
                                 debug!("🦀 closest_target:{:?}", closest_target);
+                                party.claim(target_entity, *actor);
+
                                 // Look direction
                                 sprite.flip_x = actor_position.xy.x > closest_target.xy.x;
 
                                 // Lock target
+                                actor_target_at.entity = Some(target_entity);
                                 actor_target_at.last_position = Some(closest_target);
 
                                 // Action
@@ -184,6 +169,7 @@ pub fn loot_action_system<T, U>(
                             None => {
                                 debug!("🦀 find_closest_target_with_entity NOT FOUND");
                                 // Unlock target
+                                actor_target_at.entity = None;
                                 actor_target_at.last_position = None;
 
                                 // Action
@@ -1,4 +1,8 @@
+pub mod avoid;
 pub mod behavior;
 pub mod fight;
 pub mod loot;
+pub mod order;
+pub mod party;
+pub mod patrol;
 pub mod thinker;
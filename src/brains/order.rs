@@ -0,0 +1,354 @@
+use std::collections::VecDeque;
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use big_brain::prelude::*;
+
+use crate::characters::actions::{Act, Action};
+use crate::characters::effect::{speed_multiplier, StatusEffects};
+use crate::characters::entities::CharacterKind;
+use crate::core::gate::Opened;
+use crate::core::grave::Sealed;
+use crate::core::lever::Switched;
+use crate::core::map::{
+    find_adjacent_walkables, get_map_from_position, get_position_from_map, MapPosition,
+};
+use crate::core::position::Position;
+use crate::core::scene::ChunkMap;
+
+use super::fight::TargetAt;
+use super::loot::Looted;
+use super::thinker::{find_next_step, MAX_DISTANCE, MOVEMENT_SPEED};
+
+// Command given by the player, e.g. by clicking a cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Order {
+    GoTo(MapPosition),
+    Attack(Entity),
+    Open(Entity),
+}
+
+#[derive(Component, Debug, Default)]
+pub struct Orders(pub VecDeque<Order>);
+
+impl Orders {
+    // After the others when queued, instead of them otherwise.
+    pub fn give(&mut self, order: Order, is_queued: bool) {
+        if !is_queued {
+            self.0.clear();
+        }
+        self.0.push_back(order);
+    }
+}
+
+#[derive(Clone, Component, Debug, ScorerBuilder)]
+pub struct OrderScorer;
+
+#[derive(Clone, Component, Debug, ActionBuilder)]
+pub struct FollowOrder;
+
+pub fn get_orders(kind: &CharacterKind, entity_commands: &mut EntityCommands) {
+    match kind {
+        CharacterKind::Human => {
+            entity_commands.insert(Orders::default());
+        }
+        CharacterKind::Monster | CharacterKind::Animal => (),
+    }
+}
+
+pub fn order_scorer_system(
+    orders: Query<&Orders>,
+    mut query: Query<(&Actor, &mut Score), With<OrderScorer>>,
+) {
+    for (Actor(actor), mut score) in &mut query {
+        if let Ok(orders) = orders.get(*actor) {
+            // Player always win over the AI.
+            score.set(if orders.0.is_empty() { 0. } else { 1. });
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn follow_order_action_system(
+    time: Res<Time>,
+    chunk_map: Res<ChunkMap>,
+    mut characters: Query<(
+        &mut Orders,
+        &mut Position,
+        &mut Action,
+        &mut TargetAt,
+        &mut Sprite,
+        Option<&StatusEffects>,
+    )>,
+    targets: Query<
+        (
+            &Position,
+            Option<&Looted>,
+            Option<&Opened>,
+            Option<&Switched>,
+            Option<&Sealed>,
+        ),
+        Without<Orders>,
+    >,
+    mut action_query: Query<(&Actor, &mut ActionState, &FollowOrder, &ActionSpan)>,
+) {
+    for (Actor(actor), mut state, _follow_order, span) in &mut action_query {
+        let _guard = span.span().enter();
+
+        let Ok((
+            mut orders,
+            mut actor_position,
+            mut actor_action,
+            mut actor_target_at,
+            mut sprite,
+            effects,
+        )) = characters.get_mut(*actor)
+        else {
+            continue;
+        };
+
+        match *state {
+            ActionState::Requested => {
+                debug!("📣 Follow order: {:?}", orders.0.front());
+                *state = ActionState::Executing;
+            }
+            ActionState::Executing => {
+                let Some(order) = orders.0.front().cloned() else {
+                    *state = ActionState::Success;
+                    continue;
+                };
+
+                // Where to go, how close is enough and whether it's already done.
+                let (goal, reach, is_done) = match &order {
+                    Order::GoTo(at) => (
+                        Some(get_position_from_map(at.x, at.y, None).translation.xy()),
+                        1.,
+                        false,
+                    ),
+                    // Corpses lose their `Position`, so a dead target is done too
+                    Order::Attack(entity) => match targets.get(*entity) {
+                        Ok((position, ..)) => (Some(position.xy), MAX_DISTANCE, false),
+                        Err(_) => (None, 0., true),
+                    },
+                    Order::Open(entity) => match targets.get(*entity) {
+                        Ok((position, looted, opened, switched, sealed)) => (
+                            Some(position.xy),
+                            MAX_DISTANCE,
+                            looted.is_some()
+                                || opened.is_some()
+                                || switched.is_some()
+                                || sealed.is_some(),
+                        ),
+                        Err(_) => (None, 0., true),
+                    },
+                };
+
+                let Some(goal) = goal.filter(|_| !is_done) else {
+                    println!("📣 Order done: {:?}", order);
+                    orders.0.pop_front();
+
+                    // Unlock target
+                    actor_target_at.entity = None;
+                    actor_target_at.last_position = None;
+
+                    if actor_action.0 != Act::Die {
+                        *actor_action = Action(Act::Idle);
+                    }
+
+                    if orders.0.is_empty() {
+                        *state = ActionState::Success;
+                    }
+                    continue;
+                };
+
+                // Unwalkable target, e.g. a gate, stand on the closest cell next to it
+                let (goal_x, goal_y) = get_map_from_position(goal, None);
+                let is_goal_walkable = chunk_map
+                    .walkables
+                    .get(goal_y)
+                    .and_then(|row| row.get(goal_x))
+                    .copied()
+                    .unwrap_or(false);
+                let walk_to = if is_goal_walkable {
+                    Some(goal)
+                } else {
+                    find_adjacent_walkables(
+                        &chunk_map.walkables,
+                        &MapPosition {
+                            x: goal_x,
+                            y: goal_y,
+                        },
+                    )
+                    .into_iter()
+                    .map(|at| get_position_from_map(at.x, at.y, None).translation.xy())
+                    .min_by(|a, b| {
+                        a.distance_squared(actor_position.xy)
+                            .total_cmp(&b.distance_squared(actor_position.xy))
+                    })
+                };
+
+                let distance = (goal - actor_position.xy).length();
+                let is_beside = walk_to.is_some_and(|xy| xy.distance(actor_position.xy) <= 1.);
+                if distance > reach && !is_beside {
+                    match walk_to
+                        .and_then(|xy| find_next_step(&chunk_map.walkables, actor_position.xy, xy))
+                    {
+                        Some(next_xy) => {
+                            let delta = next_xy - actor_position.xy;
+                            let step_size =
+                                time.delta_seconds() * MOVEMENT_SPEED * speed_multiplier(effects);
+                            let step = delta.normalize_or_zero() * step_size.min(delta.length());
+
+                            // Look direction
+                            sprite.flip_x = step.x < 0.;
+
+                            actor_position.xy += step;
+                            *actor_action = Action(Act::Walk);
+                        }
+                        None => {
+                            println!("📣 No way to {:?}, skip.", order);
+                            orders.0.pop_front();
+                            *actor_action = Action(Act::Idle);
+                        }
+                    }
+                    continue;
+                }
+
+                // Arrived
+                match &order {
+                    Order::GoTo(_) => {
+                        orders.0.pop_front();
+                        *actor_action = Action(Act::Idle);
+                    }
+                    Order::Attack(entity) | Order::Open(entity) => {
+                        // Look direction
+                        sprite.flip_x = actor_position.xy.x > goal.x;
+
+                        // Lock target
+                        actor_target_at.entity = Some(*entity);
+                        actor_target_at.last_position = Some(Position { xy: goal });
+
+                        *actor_action = match order {
+                            Order::Attack(_) => Action(Act::Attack),
+                            _ => Action(Act::Open),
+                        };
+                    }
+                }
+            }
+            // All Actions should make sure to handle cancellations!
+            ActionState::Cancelled => {
+                debug!("📣 Order was interrupted.");
+                *state = ActionState::Failure;
+
+                if actor_action.0 != Act::Die {
+                    *actor_action = Action(Act::Idle);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        brains::{
+            party::Party,
+            thinker::{
+                get_thinker, guard_system, guarding_scorer_system, move_to_nearest_system, Guard,
+            },
+        },
+        core::point::Exit,
+    };
+    use bevy::time::TimeUpdateStrategy;
+    use big_brain::{BigBrainPlugin, BigBrainSet};
+    use std::time::Duration;
+
+    const GO_TO: MapPosition = MapPosition { x: 1, y: 4 };
+
+    #[test]
+    fn test_give_order() {
+        let mut orders = Orders::default();
+        orders.give(Order::GoTo(MapPosition { x: 1, y: 1 }), false);
+        orders.give(Order::GoTo(GO_TO), true);
+        assert_eq!(orders.0.len(), 2);
+
+        // Without shift the queue is replaced
+        orders.give(Order::GoTo(GO_TO), false);
+        assert_eq!(orders.0, VecDeque::from([Order::GoTo(GO_TO)]));
+    }
+
+    // A hero between the order and the exit, the AI wants to leave.
+    fn setup() -> (App, Entity) {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, BigBrainPlugin::new(Update)))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                1. / 30.,
+            )))
+            .insert_resource(ChunkMap {
+                walkables: vec![vec![true; 8]; 8],
+                ..default()
+            })
+            .init_resource::<Party>()
+            .add_systems(Update, guard_system)
+            .add_systems(
+                Update,
+                (order_scorer_system, guarding_scorer_system).in_set(BigBrainSet::Scorers),
+            )
+            .add_systems(
+                Update,
+                (follow_order_action_system, move_to_nearest_system::<Exit>)
+                    .in_set(BigBrainSet::Actions),
+            );
+
+        let exit_xy = get_position_from_map(6, 4, None).translation.xy();
+        app.world_mut().spawn((Exit, Position { xy: exit_xy }));
+
+        let hero_xy = get_position_from_map(4, 4, None).translation.xy();
+        let hero = app
+            .world_mut()
+            .spawn((
+                CharacterKind::Human,
+                Position { xy: hero_xy },
+                Action(Act::Idle),
+                TargetAt::default(),
+                Sprite::default(),
+                Orders(VecDeque::from([Order::GoTo(GO_TO)])),
+                Guard::new(75.0, 10.0),
+                get_thinker(&CharacterKind::Human),
+            ))
+            .id();
+
+        (app, hero)
+    }
+
+    #[test]
+    fn test_order_beats_the_ai() {
+        let (mut app, hero) = setup();
+        let from = app.world().get::<Position>(hero).unwrap().xy;
+
+        // Full concern ties the order, which still comes first
+        for _ in 0..90 {
+            app.update();
+        }
+
+        let xy = app.world().get::<Position>(hero).unwrap().xy;
+        assert!(xy.x < from.x);
+        assert!(app.world().get::<Guard>(hero).unwrap().concern >= 100.);
+    }
+
+    #[test]
+    fn test_go_to_drains_the_queue() {
+        let (mut app, hero) = setup();
+
+        let mut ticks = 0;
+        while !app.world().get::<Orders>(hero).unwrap().0.is_empty() {
+            assert!(ticks < 300, "Expected the hero to get there");
+            app.update();
+            ticks += 1;
+        }
+
+        let xy = app.world().get::<Position>(hero).unwrap().xy;
+        assert_eq!(get_map_from_position(xy, None), (GO_TO.x, GO_TO.y));
+    }
+}
@@ -0,0 +1,237 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    characters::{bar::Health, entities::CharacterKind},
+    core::{chest::Chest, faction::FactionTable, position::Position, stage::CharacterDef},
+};
+
+use super::loot::Looted;
+
+// What the heroes know together, so one sight is enough for all.
+#[derive(Resource, Default, Debug)]
+pub struct Party {
+    pub known_foes: HashMap<Entity, Vec2>,
+    pub known_chests: HashMap<Entity, Vec2>,
+    // Chest -> hero on the way, others pick another one.
+    pub chest_claims: HashMap<Entity, Entity>,
+    // Foe everyone hit first.
+    pub focus: Option<Entity>,
+}
+
+impl Party {
+    pub fn is_member(kind: &CharacterKind) -> bool {
+        *kind == CharacterKind::Human
+    }
+
+    pub fn is_claimed_by_other(&self, chest: Entity, hero: Entity) -> bool {
+        self.chest_claims
+            .get(&chest)
+            .is_some_and(|claimed_by| *claimed_by != hero)
+    }
+
+    // Focused foe for a party member, if any.
+    pub fn focus_for(&self, kind: &CharacterKind) -> Option<Entity> {
+        self.focus.filter(|_| Party::is_member(kind))
+    }
+
+    // One chest per hero, a new claim drop the previous one.
+    pub fn claim(&mut self, chest: Entity, hero: Entity) {
+        self.chest_claims
+            .retain(|_, claimed_by| *claimed_by != hero);
+        self.chest_claims.insert(chest, hero);
+    }
+}
+
+pub fn reset_party(mut party: ResMut<Party>) {
+    *party = Party::default();
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_party_knowledge(
+    mut party: ResMut<Party>,
+    factions: Res<FactionTable>,
+    characters: Query<(Entity, &Position, &CharacterKind, &CharacterDef, &Health)>,
+    chests: Query<(Entity, &Position), (With<Chest>, Without<Looted>)>,
+) {
+    let heroes = characters
+        .iter()
+        .filter(|(_, _, kind, ..)| Party::is_member(kind))
+        .map(|(entity, position, _, character_def, ..)| {
+            (entity, position.xy, character_def.line_of_sight)
+        })
+        .collect::<Vec<_>>();
+    let is_seen = |xy: Vec2| {
+        heroes
+            .iter()
+            .any(|(_, hero_xy, line_of_sight)| hero_xy.distance(xy) < *line_of_sight)
+    };
+
+    // Foes, keep tracking the ones already seen
+    let mut known_foes = HashMap::default();
+    for (entity, position, kind, ..) in characters.iter() {
+        if !factions.is_hostile(&CharacterKind::Human, kind) {
+            continue;
+        }
+        if party.known_foes.contains_key(&entity) || is_seen(position.xy) {
+            known_foes.insert(entity, position.xy);
+        }
+    }
+    for entity in known_foes.keys() {
+        if !party.known_foes.contains_key(entity) {
+            println!("👀 Party spotted:{:?}", entity);
+        }
+    }
+    party.known_foes = known_foes;
+
+    // Chests, forget the looted ones
+    let mut known_chests = HashMap::default();
+    for (entity, position) in chests.iter() {
+        if party.known_chests.contains_key(&entity) || is_seen(position.xy) {
+            known_chests.insert(entity, position.xy);
+        }
+    }
+    party.known_chests = known_chests;
+
+    // Claims end with the chest or the hero
+    let Party {
+        known_chests,
+        chest_claims,
+        ..
+    } = &mut *party;
+    chest_claims.retain(|chest, hero| {
+        known_chests.contains_key(chest) && heroes.iter().any(|(entity, ..)| entity == hero)
+    });
+
+    // Focus the weakest known foe until it's down
+    let is_focus_alive = party
+        .focus
+        .is_some_and(|focus| party.known_foes.contains_key(&focus));
+    if !is_focus_alive {
+        party.focus = party
+            .known_foes
+            .keys()
+            .filter_map(|entity| characters.get(*entity).ok())
+            .min_by(|(.., a), (.., b)| a.value.total_cmp(&b.value))
+            .map(|(entity, ..)| entity);
+
+        if let Some(focus) = party.focus {
+            println!("🎯 Party focus:{:?}", focus);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> App {
+        let mut app = App::new();
+        app.init_resource::<Party>()
+            .init_resource::<FactionTable>()
+            .add_systems(Update, update_party_knowledge);
+        app
+    }
+
+    fn spawn(app: &mut App, kind: CharacterKind, xy: Vec2, health: f32) -> Entity {
+        app.world_mut()
+            .spawn((
+                Position { xy },
+                kind,
+                CharacterDef {
+                    kind,
+                    line_of_sight: 100.,
+                    ..default()
+                },
+                Health::new_full(health),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn test_claim_one_chest_per_hero() {
+        let mut party = Party::default();
+        let (hero, other) = (Entity::from_raw(1), Entity::from_raw(2));
+        let (chest_a, chest_b) = (Entity::from_raw(10), Entity::from_raw(11));
+
+        party.claim(chest_a, hero);
+        assert!(party.is_claimed_by_other(chest_a, other));
+        assert!(!party.is_claimed_by_other(chest_a, hero));
+
+        party.claim(chest_b, hero);
+        assert!(!party.is_claimed_by_other(chest_a, other));
+        assert!(party.is_claimed_by_other(chest_b, other));
+    }
+
+    #[test]
+    fn test_focus_only_for_members() {
+        let party = Party {
+            focus: Some(Entity::from_raw(1)),
+            ..default()
+        };
+
+        assert!(party.focus_for(&CharacterKind::Human).is_some());
+        assert!(party.focus_for(&CharacterKind::Monster).is_none());
+    }
+
+    #[test]
+    fn test_known_foes_stay_known() {
+        let mut app = setup();
+        spawn(&mut app, CharacterKind::Human, Vec2::ZERO, 100.);
+        let foe = spawn(&mut app, CharacterKind::Monster, Vec2::new(50., 0.), 100.);
+        let far = spawn(&mut app, CharacterKind::Monster, Vec2::new(500., 0.), 100.);
+
+        app.update();
+        let party = app.world().resource::<Party>();
+        assert!(party.known_foes.contains_key(&foe));
+        assert!(!party.known_foes.contains_key(&far));
+
+        // Out of sight, still tracked
+        app.world_mut().get_mut::<Position>(foe).unwrap().xy = Vec2::new(400., 0.);
+        app.update();
+        let party = app.world().resource::<Party>();
+        assert_eq!(party.known_foes.get(&foe), Some(&Vec2::new(400., 0.)));
+    }
+
+    #[test]
+    fn test_focus_weakest_until_down() {
+        let mut app = setup();
+        spawn(&mut app, CharacterKind::Human, Vec2::ZERO, 100.);
+        let strong = spawn(&mut app, CharacterKind::Monster, Vec2::new(20., 0.), 100.);
+        let weak = spawn(&mut app, CharacterKind::Monster, Vec2::new(40., 0.), 30.);
+
+        app.update();
+        assert_eq!(app.world().resource::<Party>().focus, Some(weak));
+
+        // Down, move on to the next one
+        app.world_mut().despawn(weak);
+        app.update();
+        assert_eq!(app.world().resource::<Party>().focus, Some(strong));
+    }
+
+    #[test]
+    fn test_claim_dropped_with_looted_chest() {
+        let mut app = setup();
+        let hero = spawn(&mut app, CharacterKind::Human, Vec2::ZERO, 100.);
+        let chest = app
+            .world_mut()
+            .spawn((
+                Position {
+                    xy: Vec2::new(10., 0.),
+                },
+                Chest {
+                    status: crate::core::chest::ChestState::Close,
+                    key: None,
+                },
+            ))
+            .id();
+
+        app.update();
+        app.world_mut().resource_mut::<Party>().claim(chest, hero);
+
+        app.world_mut().entity_mut(chest).insert(Looted);
+        app.update();
+        let party = app.world().resource::<Party>();
+        assert!(!party.known_chests.contains_key(&chest));
+        assert!(party.chest_claims.is_empty());
+    }
+}
@@ -0,0 +1,308 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use big_brain::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    characters::{
+        actions::{Act, Action},
+        effect::{speed_multiplier, StatusEffects},
+        entities::CharacterKind,
+    },
+    core::{
+        chest::Chest,
+        map::{convert_map_to_screen, get_position_from_map, MapPosition},
+        position::Position,
+        scene::ChunkMap,
+        stage::CharacterDef,
+    },
+};
+
+use super::thinker::{find_next_step, Guard, MAX_DISTANCE};
+
+// Longest pause at a waypoint, for a fully concerned guard.
+const MAX_LINGER: f32 = 3.;
+
+// Where to patrol, e.g. `- grave`, `- chest` or `- cell: f3`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Waypoint {
+    Grave,
+    Chest,
+    Exit,
+    Cell(String),
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct Patrol {
+    pub waypoints: Vec<Waypoint>,
+    pub next: usize,
+    pub linger: f32,
+}
+
+#[derive(Clone, Component, Debug, ActionBuilder)]
+pub struct FollowPatrol {
+    speed: f32,
+}
+
+impl FollowPatrol {
+    pub fn new(speed: f32) -> Self {
+        Self { speed }
+    }
+}
+
+// Rally between grave and chest unless the stage say otherwise.
+pub fn get_patrol(character_def: &CharacterDef, entity_commands: &mut EntityCommands) {
+    match character_def.kind {
+        CharacterKind::Monster => {
+            let waypoints = match character_def.patrol.is_empty() {
+                true => vec![Waypoint::Chest, Waypoint::Grave],
+                false => character_def.patrol.clone(),
+            };
+
+            entity_commands.insert(Patrol {
+                waypoints,
+                next: 0,
+                linger: 0.,
+            });
+        }
+        CharacterKind::Human | CharacterKind::Animal => (),
+    }
+}
+
+fn get_xy(at: &MapPosition) -> Vec2 {
+    get_position_from_map(at.x, at.y, None).translation.xy()
+}
+
+fn find_waypoint_position(
+    waypoint: &Waypoint,
+    from: Vec2,
+    chunk_map: &ChunkMap,
+    chests: &Query<&Position, (With<Chest>, Without<Patrol>)>,
+) -> Option<Vec2> {
+    let closest = |candidates: Vec<Vec2>| {
+        candidates.into_iter().min_by(|a, b| {
+            a.distance_squared(from)
+                .total_cmp(&b.distance_squared(from))
+        })
+    };
+
+    match waypoint {
+        Waypoint::Grave => closest(chunk_map.graves.iter().map(get_xy).collect()),
+        Waypoint::Chest => closest(chests.iter().map(|position| position.xy).collect()),
+        Waypoint::Exit => Some(get_xy(&chunk_map.exit)),
+        Waypoint::Cell(cell) => {
+            convert_map_to_screen(cell.clone()).map(|(x, y)| get_xy(&MapPosition { x, y }))
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn follow_patrol_action_system(
+    time: Res<Time>,
+    chunk_map: Res<ChunkMap>,
+    mut characters: Query<(
+        &mut Patrol,
+        &Guard,
+        &mut Position,
+        &mut Action,
+        &mut Sprite,
+        Option<&StatusEffects>,
+    )>,
+    chests: Query<&Position, (With<Chest>, Without<Patrol>)>,
+    mut action_query: Query<(&Actor, &mut ActionState, &FollowPatrol, &ActionSpan)>,
+) {
+    for (Actor(actor), mut state, follow_patrol, span) in &mut action_query {
+        let _guard = span.span().enter();
+
+        let Ok((mut patrol, guard, mut actor_position, mut actor_action, mut sprite, effects)) =
+            characters.get_mut(*actor)
+        else {
+            continue;
+        };
+
+        match *state {
+            ActionState::Requested => {
+                // Pick up where we left, e.g. after a fight.
+                debug!("🚶 Patrol to waypoint {}", patrol.next);
+                *state = ActionState::Executing;
+            }
+            ActionState::Executing => {
+                if patrol.waypoints.is_empty() {
+                    *state = ActionState::Failure;
+                    continue;
+                }
+
+                // Catch a breath, longer when worried.
+                if patrol.linger > 0. {
+                    patrol.linger -= time.delta_seconds();
+                    *actor_action = Action(Act::Idle);
+                    continue;
+                }
+
+                let next = patrol.next % patrol.waypoints.len();
+                let goal = find_waypoint_position(
+                    &patrol.waypoints[next],
+                    actor_position.xy,
+                    &chunk_map,
+                    &chests,
+                );
+
+                let Some(goal) = goal else {
+                    // Nothing there, e.g. no chest left
+                    patrol.next = (next + 1) % patrol.waypoints.len();
+                    continue;
+                };
+
+                if goal.distance(actor_position.xy) <= MAX_DISTANCE {
+                    patrol.next = (next + 1) % patrol.waypoints.len();
+                    patrol.linger = guard.concern / 100. * MAX_LINGER;
+                    *actor_action = Action(Act::Idle);
+                    continue;
+                }
+
+                match find_next_step(&chunk_map.walkables, actor_position.xy, goal) {
+                    Some(next_xy) => {
+                        let delta = next_xy - actor_position.xy;
+                        let step_size =
+                            time.delta_seconds() * follow_patrol.speed * speed_multiplier(effects);
+                        let step = delta.normalize_or_zero() * step_size.min(delta.length());
+
+                        // Look direction
+                        sprite.flip_x = step.x < 0.;
+
+                        actor_position.xy += step;
+                        *actor_action = Action(Act::Walk);
+                    }
+                    None => {
+                        // No way through e.g. closed gate, try the next one.
+                        patrol.next = (next + 1) % patrol.waypoints.len();
+                        *actor_action = Action(Act::Idle);
+                    }
+                }
+            }
+            ActionState::Cancelled => {
+                debug!("🚶 Patrol was interrupted.");
+                *state = ActionState::Failure;
+
+                if actor_action.0 != Act::Die {
+                    *actor_action = Action(Act::Idle);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brains::thinker::{guarding_scorer_system, Duty, MOVEMENT_SPEED};
+    use bevy::time::TimeUpdateStrategy;
+    use big_brain::{BigBrainPlugin, BigBrainSet};
+    use std::time::Duration;
+
+    fn get_cell_xy(cell: &str) -> Vec2 {
+        let (x, y) = convert_map_to_screen(cell.to_string()).unwrap();
+        get_xy(&MapPosition { x, y })
+    }
+
+    // A skeleton standing on `at`, patrolling the `cells`.
+    fn setup(cells: &[&str], at: &str, next: usize, concern: f32) -> (App, Entity) {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, BigBrainPlugin::new(Update)))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                1. / 30.,
+            )))
+            .insert_resource(ChunkMap {
+                walkables: vec![vec![true; 8]; 8],
+                ..default()
+            })
+            .add_systems(Update, guarding_scorer_system.in_set(BigBrainSet::Scorers))
+            .add_systems(
+                Update,
+                follow_patrol_action_system.in_set(BigBrainSet::Actions),
+            );
+
+        let skeleton = app
+            .world_mut()
+            .spawn((
+                Patrol {
+                    waypoints: cells
+                        .iter()
+                        .map(|cell| Waypoint::Cell(cell.to_string()))
+                        .collect(),
+                    next,
+                    linger: 0.,
+                },
+                Guard::new(concern, 0.),
+                Position {
+                    xy: get_cell_xy(at),
+                },
+                Action(Act::Idle),
+                Sprite::default(),
+                Thinker::build()
+                    .picker(Highest)
+                    .when(Duty, FollowPatrol::new(MOVEMENT_SPEED)),
+            ))
+            .id();
+
+        (app, skeleton)
+    }
+
+    fn get_skeleton_patrol(app: &App, skeleton: Entity) -> &Patrol {
+        app.world().get::<Patrol>(skeleton).unwrap()
+    }
+
+    // Update until the waypoint is reached, then how long it lingers.
+    fn get_linger(concern: f32) -> f32 {
+        let (mut app, skeleton) = setup(&["b2", "e2"], "b2", 0, concern);
+        for _ in 0..30 {
+            app.update();
+            if get_skeleton_patrol(&app, skeleton).next != 0 {
+                break;
+            }
+        }
+        get_skeleton_patrol(&app, skeleton).linger
+    }
+
+    #[test]
+    fn test_patrol_wraps_around() {
+        let (mut app, skeleton) = setup(&["b2", "e2"], "e2", 1, 50.);
+        for _ in 0..10 {
+            app.update();
+        }
+
+        assert_eq!(get_skeleton_patrol(&app, skeleton).next, 0);
+    }
+
+    #[test]
+    fn test_linger_scales_with_concern() {
+        assert_eq!(get_linger(50.), MAX_LINGER / 2.);
+        assert_eq!(get_linger(100.), MAX_LINGER);
+    }
+
+    #[test]
+    fn test_patrol_resumes_after_cancel() {
+        let (mut app, skeleton) = setup(&["b2", "e2", "e5"], "b2", 0, 10.);
+        for _ in 0..30 {
+            app.update();
+        }
+        assert_eq!(get_skeleton_patrol(&app, skeleton).next, 1);
+        let x = app.world().get::<Position>(skeleton).unwrap().xy.x;
+
+        // e.g. a foe in sight
+        let mut actions = app
+            .world_mut()
+            .query_filtered::<&mut ActionState, With<FollowPatrol>>();
+        for mut state in actions.iter_mut(app.world_mut()) {
+            *state = ActionState::Cancelled;
+        }
+        for _ in 0..30 {
+            app.update();
+        }
+
+        // Still on the way to e2, not back to b2
+        assert_eq!(get_skeleton_patrol(&app, skeleton).next, 1);
+        assert!(app.world().get::<Position>(skeleton).unwrap().xy.x > x);
+    }
+}
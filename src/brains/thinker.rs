@@ -4,25 +4,25 @@ use big_brain::prelude::*;
 
 use crate::characters::actions::{Act, Action};
 use crate::characters::bar::Health;
-use crate::characters::entities::CharacterId;
+use crate::characters::effect::{speed_multiplier, StatusEffects};
+use crate::characters::entities::CharacterKind;
 use crate::core::chest::Chest;
-use crate::core::grave::Grave;
+use crate::core::faction::FactionTable;
 use crate::core::map::{find_path, get_map_from_position, get_position_from_map};
 use crate::core::point::Exit;
 use crate::core::position::Position;
 use crate::core::scene::ChunkMap;
-use crate::core::stage::{CharacterInfo, Human, Monster, Npc};
-use crate::core::state::GameState;
-use crate::dialogs::ask::{AskDialogContent, AskDialogEvent};
-use crate::get_type_id;
-use crate::interactions::damage::Death;
 use std::cmp::Ordering;
 use std::fmt::Debug;
 
+use super::avoid::{Avoid, AvoidScorer};
 use super::fight::{Fight, FightScorer};
 use super::loot::{Loot, LootScorer, Looted};
+use super::order::{FollowOrder, OrderScorer};
+use super::party::Party;
+use super::patrol::FollowPatrol;
 
-const MAX_DISTANCE: f32 = 32.;
+pub const MAX_DISTANCE: f32 = 32.;
 
 #[derive(Component, Debug)]
 pub struct Guard {
@@ -58,9 +58,9 @@ pub struct LookAround {
 #[allow(clippy::type_complexity)]
 pub fn guard_action_system<T: Component + Debug + Clone>(
     time: Res<Time>,
-    mut guards: Query<(&Position, &mut Guard), (Without<T>, Without<Death>)>,
-    targets: Query<&Position, (With<T>, Without<Death>)>,
-    mut query: Query<(&Actor, &mut ActionState, &LookAround, &ActionSpan), Without<Death>>,
+    mut guards: Query<(&Position, &mut Guard), Without<T>>,
+    targets: Query<&Position, With<T>>,
+    mut query: Query<(&Actor, &mut ActionState, &LookAround, &ActionSpan)>,
 ) {
     // Loop through all actions, just like you'd loop over all entities in any other query.
     for (Actor(actor), mut state, look_around, span) in &mut query {
@@ -115,7 +115,7 @@ pub struct Duty;
 #[allow(clippy::type_complexity)]
 pub fn guarding_scorer_system(
     guards: Query<&Guard>,
-    mut query: Query<(&Actor, &mut Score), (With<Duty>, Without<Death>)>,
+    mut query: Query<(&Actor, &mut Score), With<Duty>>,
 ) {
     for (Actor(actor), mut score) in &mut query {
         if let Ok(guard) = guards.get(*actor) {
@@ -124,17 +124,14 @@ pub fn guarding_scorer_system(
     }
 }
 
-const MOVEMENT_SPEED: f32 = 32.;
+pub const MOVEMENT_SPEED: f32 = 32.;
 
-pub fn get_thinker<T>() -> ThinkerBuilder
-where
-    T: CharacterInfo + Clone + Debug + 'static,
-{
-    match get_type_id!(T) {
-        id if id == get_type_id!(Human) => {
+pub fn get_thinker(kind: &CharacterKind) -> ThinkerBuilder {
+    match kind {
+        CharacterKind::Human => {
             let move_and_exit = Steps::build()
                 .label("MoveAndLExit")
-                .step(MoveToNearest::<Exit>::new(MOVEMENT_SPEED, 0.))
+                .step(MoveToNearest::<Exit>::new(MOVEMENT_SPEED, 0., false))
                 .step(LookAround {
                     per_second: 25.0,
                     distance: MAX_DISTANCE,
@@ -142,9 +139,13 @@ where
 
             let move_and_loot = Steps::build()
                 .label("MoveAndLoot")
-                .step(MoveToNearest::<Chest>::new(MOVEMENT_SPEED, MAX_DISTANCE))
+                .step(MoveToNearest::<Chest>::new(
+                    MOVEMENT_SPEED,
+                    MAX_DISTANCE,
+                    true,
+                ))
                 .step(Loot {})
-                .step(MoveToNearest::<Exit>::new(MOVEMENT_SPEED, 0.))
+                .step(MoveToNearest::<Exit>::new(MOVEMENT_SPEED, 0., false))
                 .step(LookAround {
                     per_second: 25.0,
                     distance: MAX_DISTANCE,
@@ -152,41 +153,39 @@ where
 
             let move_and_fight = Steps::build()
                 .label("MoveAndFight")
-                .step(MoveToNearest::<Monster>::new(MOVEMENT_SPEED, MAX_DISTANCE))
+                .step(MoveToFoe::new(MOVEMENT_SPEED, MAX_DISTANCE))
                 .step(Fight {});
 
             Thinker::build()
                 .label("GuardingThinker")
                 .picker(Highest)
+                // Player orders come first
+                .when(OrderScorer, FollowOrder)
+                // Run when morale break
+                .when(AvoidScorer, Avoid::new(MOVEMENT_SPEED))
                 .when(LootScorer, move_and_loot)
                 .when(FightScorer, move_and_fight)
                 .when(Duty, move_and_exit)
         }
-        id if id == get_type_id!(Monster) => {
-            let move_and_guard = Steps::build()
-                .label("MoveAndGuard")
-                .step(MoveToNearest::<Chest>::new(MOVEMENT_SPEED, MAX_DISTANCE))
-                .step(LookAround {
-                    per_second: 25.0,
-                    distance: MAX_DISTANCE,
-                })
-                .step(MoveToNearest::<Grave>::new(MOVEMENT_SPEED, MAX_DISTANCE));
-
+        CharacterKind::Monster => {
             let move_and_fight = Steps::build()
                 .label("MoveAndFight")
-                .step(MoveToNearest::<Human>::new(MOVEMENT_SPEED, MAX_DISTANCE))
+                .step(MoveToFoe::new(MOVEMENT_SPEED, MAX_DISTANCE))
                 .step(Fight {});
 
             Thinker::build()
                 .label("GuardingThinker")
                 .picker(Highest)
+                .when(AvoidScorer, Avoid::new(MOVEMENT_SPEED))
                 .when(FightScorer, move_and_fight)
-                .when(Duty, move_and_guard)
-        }
-        id if id == get_type_id!(Npc) => {
-            todo!()
+                .when(Duty, FollowPatrol::new(MOVEMENT_SPEED))
         }
-        _ => todo!(),
+        // Never fight, only wander and run when scared
+        CharacterKind::Animal => Thinker::build()
+            .label("WanderingThinker")
+            .picker(Highest)
+            .when(AvoidScorer, Avoid::new(MOVEMENT_SPEED))
+            .when(Duty, FollowPatrol::new(MOVEMENT_SPEED)),
     }
 }
 
@@ -197,50 +196,61 @@ pub struct MoveToNearest<T: Component + Debug + Clone> {
     _marker: std::marker::PhantomData<T>,
     speed: f32,
     distance: f32,
+    // Let the party know the target is taken, e.g. a chest
+    claims: bool,
 }
 
 impl<T: Component + Debug + Clone> MoveToNearest<T> {
-    pub fn new(speed: f32, distance: f32) -> Self {
+    pub fn new(speed: f32, distance: f32, claims: bool) -> Self {
         Self {
             _marker: std::marker::PhantomData,
             speed,
             distance,
+            claims,
         }
     }
 }
 
 #[allow(clippy::type_complexity)]
-pub fn find_closest_target_with_health<T: Component + Debug + Clone>(
-    targets: &Query<(&Health, &Position), (With<T>, Without<Death>)>,
+pub fn find_closest_foe(
+    targets: &Query<(Entity, &Health, &Position, &CharacterKind)>,
+    factions: &FactionTable,
+    actor: Entity,
+    actor_kind: &CharacterKind,
     actor_position: &Position,
-) -> Option<(f32, Position)> {
+) -> Option<(Entity, f32, Position)> {
     targets
         .iter()
-        .min_by(|(_, a_pos), (_, b_pos)| {
+        .filter(|(entity, _, _, kind)| *entity != actor && factions.is_hostile(actor_kind, kind))
+        .min_by(|(_, _, a_pos, _), (_, _, b_pos, _)| {
             let da = (a_pos.xy - actor_position.xy).length_squared();
             let db = (b_pos.xy - actor_position.xy).length_squared();
             da.partial_cmp(&db).unwrap_or(Ordering::Equal)
         })
-        .map(|(health, position)| (health.value, *position))
+        .map(|(entity, health, position, _)| (entity, health.value, *position))
 }
 
 // TODO: generic with find_closest_target
+#[allow(clippy::type_complexity)]
 pub fn find_closest_target_without_looted<T: Component + Debug + Clone>(
-    targets: &Query<&Position, (With<T>, Without<Looted>)>,
+    targets: &Query<(Entity, &Position), (With<T>, Without<Looted>)>,
+    party: &Party,
+    actor: Entity,
     actor_position: &Position,
-) -> Option<Position> {
+) -> Option<(Entity, Position)> {
     targets
         .iter()
-        .min_by(|a, b| {
+        .filter(|(entity, _)| !party.is_claimed_by_other(*entity, actor))
+        .min_by(|(_, a), (_, b)| {
             let da = (a.xy - actor_position.xy).length_squared();
             let db = (b.xy - actor_position.xy).length_squared();
             da.partial_cmp(&db).unwrap_or(Ordering::Equal)
         })
-        .cloned()
+        .map(|(entity, position)| (entity, *position))
 }
 
 pub fn find_closest_target<T: Component + Debug + Clone>(
-    targets: &Query<&Position, (With<T>, Without<Death>)>,
+    targets: &Query<&Position, With<T>>,
     actor_position: &Position,
 ) -> Option<Position> {
     targets
@@ -253,21 +263,51 @@ pub fn find_closest_target<T: Component + Debug + Clone>(
         .cloned()
 }
 
+// Like `find_closest_target` but skip chests another hero is going for.
+#[allow(clippy::type_complexity)]
+pub fn find_closest_unclaimed_target<T: Component + Debug + Clone>(
+    targets: &Query<(Entity, &Position), With<T>>,
+    party: &Party,
+    actor: Entity,
+    actor_position: &Position,
+) -> Option<(Entity, Position)> {
+    targets
+        .iter()
+        .filter(|(entity, _)| !party.is_claimed_by_other(*entity, actor))
+        .min_by(|(_, a), (_, b)| {
+            let da = (a.xy - actor_position.xy).length_squared();
+            let db = (b.xy - actor_position.xy).length_squared();
+            da.partial_cmp(&db).unwrap_or(Ordering::Equal)
+        })
+        .map(|(entity, position)| (entity, *position))
+}
+
+// Center of the next cell on the way from one position to another.
+pub fn find_next_step(walkables: &[Vec<bool>], from: Vec2, to: Vec2) -> Option<Vec2> {
+    let start = get_map_from_position(from, None);
+    let goal = get_map_from_position(to, None);
+    let path_cost = find_path(walkables, start, goal, false).ok()?;
+
+    let (x, y) = if path_cost.path.len() == 1 {
+        path_cost.path[0]
+    } else {
+        path_cost.path[1]
+    };
+
+    Some(get_position_from_map(x, y, None).translation.xy())
+}
+
 #[allow(clippy::type_complexity)]
 pub fn move_to_nearest_system<T: Component + Debug + Clone>(
     time: Res<Time>,
-    targets: Query<&Position, (With<T>, Without<Death>)>,
+    targets: Query<(Entity, &Position), With<T>>,
     mut characters: Query<
-        (&mut Position, &mut Action, &CharacterId),
-        (With<HasThinker>, Without<T>, Without<Death>),
-    >,
-    mut action_query: Query<
-        (&Actor, &mut ActionState, &MoveToNearest<T>, &ActionSpan),
-        Without<Death>,
+        (&mut Position, &mut Action, Option<&StatusEffects>),
+        (With<HasThinker>, Without<T>),
     >,
+    mut action_query: Query<(&Actor, &mut ActionState, &MoveToNearest<T>, &ActionSpan)>,
     chunk_map: Res<ChunkMap>,
-    mut ask_dialog_events: EventWriter<AskDialogEvent>,
-    mut game_state: ResMut<NextState<GameState>>,
+    mut party: ResMut<Party>,
 ) {
     if targets.is_empty() {
         return;
@@ -284,14 +324,23 @@ pub fn move_to_nearest_system<T: Component + Debug + Clone>(
             }
             ActionState::Executing => {
                 // Look up the actor's position.
-                if let Ok((mut actor_position, mut actor_action, character_id)) =
+                if let Ok((mut actor_position, mut actor_action, effects)) =
                     characters.get_mut(*actor)
                 {
                     // Look up the target closest to them.
-                    let closest_target = find_closest_target::<T>(&targets, &actor_position);
+                    let closest_target = find_closest_unclaimed_target::<T>(
+                        &targets,
+                        &party,
+                        *actor,
+                        &actor_position,
+                    );
 
                     match closest_target {
-                        Some(closest_target) => {
+                        Some((target_entity, closest_target)) => {
+                            if move_to.claims {
+                                party.claim(target_entity, *actor);
+                            }
+
                             // Find path to target
                             let start = get_map_from_position(actor_position.xy, None);
                             let goal = get_map_from_position(closest_target.xy, None);
@@ -321,7 +370,9 @@ pub fn move_to_nearest_system<T: Component + Debug + Clone>(
                                     // Too far, walk to it
                                     trace!("Stepping closer.");
 
-                                    let step_size = time.delta_seconds() * move_to.speed;
+                                    let step_size = time.delta_seconds()
+                                        * move_to.speed
+                                        * speed_multiplier(effects);
                                     let step = delta.normalize() * step_size.min(distance);
 
                                     // Move the actor.
@@ -334,28 +385,12 @@ pub fn move_to_nearest_system<T: Component + Debug + Clone>(
                                     *action_state = ActionState::Success;
 
                                     // Action
-                                    match std::any::type_name::<T>() {
-                                        // TODO: const this?
-                                        "the_rust_of_us::core::point::Exit" => {
-                                            let ask_dialog = AskDialogContent {
-                                                position: actor_position.xy,
-                                                by: character_id.clone(),
-                                                content: "I did it!".to_owned(),
-                                            };
-                                            // println!(
-                                            //     "💥 AskDialogEvent:{:?}, {:?}",
-                                            //     character_id.clone(),
-                                            //     ask_dialog
-                                            // );
-                                            ask_dialog_events.send(AskDialogEvent(ask_dialog));
-
-                                            game_state.set(GameState::Clear);
-                                        }
-                                        _ => {
-                                            *actor_action = Action(Act::Idle);
-                                        }
-                                    }
+                                    *actor_action = Action(Act::Idle);
                                 }
+                            } else {
+                                // No way through e.g. closed gate, let thinker pick again.
+                                *actor_action = Action(Act::Idle);
+                                *action_state = ActionState::Failure;
                             }
                         }
                         None => {
@@ -371,3 +406,108 @@ pub fn move_to_nearest_system<T: Component + Debug + Clone>(
         }
     }
 }
+
+#[derive(Debug, Clone, Component, ActionBuilder)]
+#[action_label = "MoveToFoe"]
+pub struct MoveToFoe {
+    speed: f32,
+    distance: f32,
+}
+
+impl MoveToFoe {
+    pub fn new(speed: f32, distance: f32) -> Self {
+        Self { speed, distance }
+    }
+}
+
+// Like `MoveToNearest` but toward whoever is hostile to the actor.
+#[allow(clippy::type_complexity)]
+pub fn move_to_foe_system(
+    time: Res<Time>,
+    mut characters: Query<(
+        Entity,
+        &mut Position,
+        &mut Action,
+        &CharacterKind,
+        Option<&StatusEffects>,
+    )>,
+    mut action_query: Query<(&Actor, &mut ActionState, &MoveToFoe, &ActionSpan)>,
+    chunk_map: Res<ChunkMap>,
+    factions: Res<FactionTable>,
+    party: Res<Party>,
+) {
+    for (Actor(actor), mut action_state, move_to, span) in &mut action_query {
+        let _guard = span.span().enter();
+
+        match *action_state {
+            ActionState::Requested => {
+                *action_state = ActionState::Executing;
+            }
+            ActionState::Executing => {
+                let Ok((_, actor_position, _, actor_kind, _)) = characters.get(*actor) else {
+                    continue;
+                };
+                let actor_xy = actor_position.xy;
+
+                // Head for the party focus, or the foe closest to them.
+                let focus_foe = party
+                    .focus_for(actor_kind)
+                    .and_then(|focus| characters.get(focus).ok())
+                    .map(|(_, position, ..)| position.xy);
+                let closest_foe = focus_foe.or_else(|| {
+                    characters
+                        .iter()
+                        .filter(|(entity, _, _, kind, _)| {
+                            entity != actor && factions.is_hostile(actor_kind, kind)
+                        })
+                        .map(|(_, position, ..)| position.xy)
+                        .min_by(|a, b| {
+                            a.distance_squared(actor_xy)
+                                .partial_cmp(&b.distance_squared(actor_xy))
+                                .unwrap_or(Ordering::Equal)
+                        })
+                });
+
+                let Ok((_, mut actor_position, mut actor_action, _, effects)) =
+                    characters.get_mut(*actor)
+                else {
+                    continue;
+                };
+
+                let Some(closest_foe) = closest_foe else {
+                    // Nobody to chase, let thinker pick again.
+                    *actor_action = Action(Act::Idle);
+                    *action_state = ActionState::Failure;
+                    continue;
+                };
+
+                if closest_foe.distance(actor_position.xy) <= move_to.distance {
+                    *action_state = ActionState::Success;
+                    *actor_action = Action(Act::Idle);
+                    continue;
+                }
+
+                match find_next_step(&chunk_map.walkables, actor_position.xy, closest_foe) {
+                    Some(next_xy) => {
+                        // Too far, walk to it
+                        let delta = next_xy - actor_position.xy;
+                        let step_size =
+                            time.delta_seconds() * move_to.speed * speed_multiplier(effects);
+                        let step = delta.normalize_or_zero() * step_size.min(delta.length());
+
+                        actor_position.xy += step;
+                        *actor_action = Action(Act::Walk);
+                    }
+                    None => {
+                        *actor_action = Action(Act::Idle);
+                        *action_state = ActionState::Failure;
+                    }
+                }
+            }
+            ActionState::Cancelled => {
+                *action_state = ActionState::Failure;
+            }
+            _ => {}
+        }
+    }
+}
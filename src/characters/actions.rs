@@ -1,12 +1,14 @@
 use bevy::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use strum_macros::{Display, EnumString};
 
 #[derive(Component, Debug)]
 #[allow(dead_code)]
 pub struct Action(pub Act);
 
-#[derive(Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, EnumString, Display)]
+#[derive(
+    Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, EnumString, Display,
+)]
 #[serde(rename_all = "snake_case")]
 #[strum(serialize_all = "snake_case")]
 pub enum Act {
@@ -26,8 +28,3 @@ pub enum LookDirection {
     #[default]
     Right,
 }
-
-#[derive(Component, Default)]
-pub struct AniAction {
-    pub act: Act,
-}
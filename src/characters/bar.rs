@@ -2,6 +2,8 @@ use bevy::prelude::*;
 use bevy_stat_bars::*;
 use std::marker::PhantomData;
 
+use super::entities::CharacterKind;
+
 #[derive(Component, Reflect, Debug)]
 #[reflect(Component)]
 pub struct Stat<T>
@@ -68,12 +70,103 @@ where
     }
 }
 
+// Change per second of a `Stat<T>`, e.g. poison or regeneration on `Health`.
+#[derive(Component, Debug)]
+pub struct StatRate<T>
+where
+    T: Component,
+{
+    pub per_second: f32,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Default for StatRate<T>
+where
+    T: Component,
+{
+    fn default() -> Self {
+        Self {
+            per_second: 0.0,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> StatRate<T>
+where
+    T: Component,
+{
+    pub fn new(per_second: f32) -> Self {
+        Self {
+            per_second,
+            ..Default::default()
+        }
+    }
+}
+
+pub fn apply_stat_rate<T>(time: Res<Time>, mut stats: Query<(&mut Stat<T>, &StatRate<T>)>)
+where
+    T: Component,
+{
+    for (mut stat, rate) in stats.iter_mut() {
+        if rate.per_second != 0.0 {
+            *stat += rate.per_second * time.delta_seconds();
+        }
+    }
+}
+
 #[derive(Component, Default, Reflect, Debug, Copy, Clone)]
 #[reflect(Component)]
 pub struct HealthValue;
 
 pub type Health = Stat<HealthValue>;
 
+#[derive(Component, Default, Reflect, Debug, Copy, Clone)]
+#[reflect(Component)]
+pub struct StaminaValue;
+
+pub type Stamina = Stat<StaminaValue>;
+
+#[derive(Component, Default, Reflect, Debug, Copy, Clone)]
+#[reflect(Component)]
+pub struct MoraleValue;
+
+pub type Morale = Stat<MoraleValue>;
+
+// Over the head, hidden until changed, smaller for monsters, `row` stack them downward.
+pub fn get_stat_bar<T>(kind: &CharacterKind, color: Color, row: usize) -> Statbar<Stat<T>>
+where
+    T: Component,
+{
+    let (length, thickness) = match kind {
+        CharacterKind::Human => (32.0, 6.0),
+        CharacterKind::Monster | CharacterKind::Animal => (20.0, 3.0),
+    };
+
+    Statbar::<Stat<T>> {
+        color,
+        empty_color: Color::from(bevy::color::palettes::css::BLACK),
+        length,
+        thickness,
+        displacement: (32. - row as f32 * (thickness + 1.)) * Vec2::Y,
+        hide: true,
+        ..Default::default()
+    }
+}
+
+pub fn get_health_bar(kind: &CharacterKind) -> Statbar<Health> {
+    get_stat_bar::<HealthValue>(kind, Color::from(bevy::color::palettes::css::RED), 0)
+}
+
+pub fn update_stat_bar<T>(mut bars: Query<(&Stat<T>, &mut Statbar<Stat<T>>), Changed<Stat<T>>>)
+where
+    T: Component,
+{
+    for (stat, mut bar) in bars.iter_mut() {
+        bar.hide = stat.value >= stat.max;
+    }
+}
+
 // pub fn adjust_stats(
 //     time: Res<Time>,
 //     keyboard_input: Res<ButtonInput<KeyCode>>,
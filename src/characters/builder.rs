@@ -1,74 +1,74 @@
-use std::fs;
-
 use crate::{
     animations::{
-        build::build_library,
-        entities::{Ani, AniType},
+        build::{build_library, AniRegistry},
+        entities::Ani,
+        loader::{AniHandles, AniSet},
         utils::get_animation_name,
     },
     brains::{
         behavior::get_behavior,
         fight::{get_fighter, TargetAt},
         loot::get_looter,
+        order::get_orders,
+        patrol::get_patrol,
+        thinker::get_thinker,
     },
     characters::{
         actions::{Act, Action, LookDirection},
-        bar::Health,
-        entities::CharacterId,
+        bar::{get_health_bar, Health, HealthValue, StatRate},
+        effect::StatusEffects,
+        stats::get_stats,
     },
     core::{
         layer::{SpriteLayer, YSort},
         map::{convert_map_to_screen, get_position_from_map},
-        position::Position,
+        position::{Position, PreviousPosition},
         scene::ChunkMap,
-        stage::{CharacterInfo, GameStage, StageInfo},
+        stage::{CharacterDef, GameStage},
     },
-    get_thinker,
+    entry::game::OnGameScreen,
+    interactions::damage::Defense,
 };
-use bevy::prelude::*;
+use bevy::{ecs::system::EntityCommands, prelude::*};
 use bevy_spritesheet_animation::prelude::*;
-use bevy_stat_bars::{Statbar, StatbarObserveEntity};
-use std::fmt::Debug;
 
-use super::{actions::AniAction, entities::CharacterKind};
+use super::{
+    entities::CharacterKind,
+    update::{AttackCooldown, Swing, SwingTimings},
+};
 
 #[derive(Bundle)]
-struct CharacterBundle<T: Component> {
+struct CharacterBundle {
     sprite_bundle: SpriteBundle,
     texture_atlas: TextureAtlas,
     spritesheet_animation: SpritesheetAnimation,
     sprite_layer: SpriteLayer,
-    marker: T,
     ysort: YSort,
-    target_at: TargetAt,
-    kind: CharacterKind,
-    ani_action: AniAction,
+    screen: OnGameScreen,
 }
 
-fn build_character<T: CharacterInfo>(
+const CLIP_FPS: u32 = 30;
+
+fn build_character(
     asset_server: &Res<AssetServer>,
     atlas_layouts: &mut ResMut<Assets<TextureAtlasLayout>>,
     library: &mut ResMut<AnimationLibrary>,
+    ani_registry: &mut ResMut<AniRegistry>,
     ani: Ani,
-    character_info: T,
-    at: (usize, usize),
-) -> CharacterBundle<T> {
-    let clip_fps = 30;
-
-    let libs = build_library(atlas_layouts, library, &ani, clip_fps);
+    character_def: &CharacterDef,
+    at: Vec2,
+) -> CharacterBundle {
+    let layout = build_library(atlas_layouts, library, ani_registry, &ani, CLIP_FPS);
 
     let texture_path = ani.texture_path.clone();
     let texture = asset_server.load(texture_path);
 
-    // let at = convert_map_to_screen(character_info.position().clone()).expect("Valid position");
-    let position = get_position_from_map(at.0, at.1, None);
-
-    let is_flip_x = match character_info.look_direction() {
+    let is_flip_x = match character_def.look_direction {
         LookDirection::Left => true,
         LookDirection::Right => false,
     };
 
-    let animation_name = get_animation_name(character_info.ani_type(), Act::Idle);
+    let animation_name = get_animation_name(&character_def.ani_type, Act::Idle);
 
     CharacterBundle {
         sprite_bundle: SpriteBundle {
@@ -77,210 +77,182 @@ fn build_character<T: CharacterInfo>(
                 flip_x: is_flip_x,
                 ..default()
             },
-            transform: Transform::from_xyz(position.translation.x, position.translation.y, 0.0)
-                .with_scale(Vec3::splat(2.0)),
+            transform: Transform::from_xyz(at.x, at.y, 0.0).with_scale(Vec3::splat(2.0)),
             ..default()
         },
         texture_atlas: TextureAtlas {
-            layout: libs[0].1.clone(),
+            layout,
             ..default()
         },
         spritesheet_animation: SpritesheetAnimation::from_id(
-            library.animation_with_name(animation_name).unwrap(),
+            ani_registry.get(animation_name).unwrap(),
         ),
         sprite_layer: SpriteLayer::Ground,
-        marker: character_info.get_clone(),
         ysort: YSort(0.0),
-        target_at: TargetAt::default(),
-        kind: *character_info.kind(),
-        ani_action: AniAction {
-            act: Act::default(),
-        },
+        screen: OnGameScreen,
     }
 }
 
-pub fn init_character<T>(
+// Everything the tick reads, no sprite needed.
+pub fn insert_character(
+    entity_commands: &mut EntityCommands,
+    ani: &Ani,
+    character: &CharacterDef,
+    at: Vec2,
+) {
+    // Statics
+    entity_commands
+        .insert((
+            character.clone(),
+            character.kind,
+            character.ani_type.clone(),
+            character.character_id.clone(),
+            TargetAt::default(),
+            AttackCooldown::new(character.attack_cooldown),
+            Swing::default(),
+            SwingTimings::new(ani, CLIP_FPS),
+        ))
+        .insert((
+            Action(character.act),
+            Position { xy: at },
+            PreviousPosition(at),
+            Health::new_full(character.health as f32),
+            Defense(character.defend as f32),
+            get_health_bar(&character.kind),
+            StatusEffects::default(),
+            StatRate::<HealthValue>::default(),
+        ));
+
+    // Dynamics
+    get_fighter(&character.kind, entity_commands);
+    get_looter(&character.kind, entity_commands);
+    get_orders(&character.kind, entity_commands);
+    get_patrol(character, entity_commands);
+    get_stats(character, entity_commands);
+    entity_commands.insert((get_thinker(&character.kind), get_behavior(&character.kind)));
+}
+
+// Shared by the stage setup and the grave spawners.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_character(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    atlas_layouts: &mut ResMut<Assets<TextureAtlasLayout>>,
+    library: &mut ResMut<AnimationLibrary>,
+    ani_registry: &mut ResMut<AniRegistry>,
+    ani: &Ani,
+    character: &CharacterDef,
+    at: Vec2,
+) -> Entity {
+    let character_bundle = build_character(
+        asset_server,
+        atlas_layouts,
+        library,
+        ani_registry,
+        ani.clone(),
+        character,
+        at,
+    );
+
+    let mut entity_commands = commands.spawn(character_bundle);
+    insert_character(&mut entity_commands, ani, character, at);
+
+    entity_commands.id()
+}
+
+// The `position` cell when walkable, else the grave closest to it.
+fn get_spawn_cell(position: &str, chunk_map: &ChunkMap) -> Option<(usize, usize)> {
+    let cell = convert_map_to_screen(position.to_string());
+    if let Some((x, y)) = cell {
+        if chunk_map.walkables.get(y).and_then(|row| row.get(x)) == Some(&true) {
+            return Some((x, y));
+        }
+    }
+
+    let (x, y) = cell.unwrap_or_default();
+    chunk_map
+        .graves
+        .iter()
+        .min_by_key(|grave| grave.x.abs_diff(x).pow(2) + grave.y.abs_diff(y).pow(2))
+        .map(|grave| (grave.x, grave.y))
+}
+
+// Heroes come in from the entrance, the rest stand at their `position`.
+pub fn get_character_cell(
+    character: &CharacterDef,
+    chunk_map: &ChunkMap,
+) -> Option<(usize, usize)> {
+    match character.kind {
+        CharacterKind::Human => Some((chunk_map.entrance.x, chunk_map.entrance.y)),
+        CharacterKind::Monster | CharacterKind::Animal => {
+            get_spawn_cell(&character.position, chunk_map)
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn init_characters(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
     mut library: ResMut<AnimationLibrary>,
+    mut ani_registry: ResMut<AniRegistry>,
+    ani_handles: Res<AniHandles>,
+    ani_sets: Res<Assets<AniSet>>,
     game_stage: Res<GameStage>,
     chunk_map: Res<ChunkMap>,
-) where
-    T: CharacterInfo + Clone + Debug + 'static,
-{
-    // let char_json = fs::read_to_string("assets/char.json").expect("Unable to read file");
-    let char_json = r#"[
-  {
-    "ani_type": "man",
-    "texture_path": "man.png",
-    "width": 96,
-    "height": 64,
-    "animations": [
-      {
-        "action_name": "idle",
-        "x": 0,
-        "y": 0,
-        "count": 9
-      },
-      {
-        "action_name": "walk",
-        "x": 0,
-        "y": 1,
-        "count": 8
-      },
-      {
-        "action_name": "attack",
-        "x": 0,
-        "y": 2,
-        "count": 10
-      },
-      {
-        "action_name": "open",
-        "x": 0,
-        "y": 3,
-        "count": 8
-      },
-      {
-        "action_name": "hurt",
-        "x": 0,
-        "y": 4,
-        "count": 8
-      },
-      {
-        "action_name": "die",
-        "x": 0,
-        "y": 5,
-        "count": 13
-      }
-    ]
-  },
-  {
-    "ani_type": "skeleton",
-    "texture_path": "skeleton.png",
-    "width": 96,
-    "height": 64,
-    "animations": [
-      {
-        "action_name": "idle",
-        "x": 0,
-        "y": 0,
-        "count": 6
-      },
-      {
-        "action_name": "walk",
-        "x": 0,
-        "y": 1,
-        "count": 8
-      },
-      {
-        "action_name": "attack",
-        "x": 0,
-        "y": 2,
-        "count": 7
-      },
-      {
-        "action_name": "hurt",
-        "x": 0,
-        "y": 3,
-        "count": 7
-      },
-      {
-        "action_name": "die",
-        "x": 0,
-        "y": 4,
-        "count": 10
-      }
-    ]
-  }
-]
-"#;
-    let characters: Vec<Ani> = serde_json::from_str(&char_json).expect("Unable to parse JSON");
+) {
+    let characters = &ani_sets
+        .get(&ani_handles.characters)
+        .expect("Expected char.ani.json loaded")
+        .0;
     println!("characters:{:?}", characters);
 
-    if let Some(character_iter) = game_stage.0.get_characters_iter_by_type::<T>() {
-        for character in character_iter {
-            println!("🔥 character:{:?}", *character);
-            if let Some(ani) = characters
-                .iter()
-                .find(|&c| c.ani_type == *character.ani_type())
-            {
-                let character_position = match *character.ani_type() {
-                    AniType::Man => {
-                        get_position_from_map(chunk_map.entrance.x, chunk_map.entrance.y, None)
-                    }
-                    AniType::Skeleton => {
-                        // TODO: more grave
-                        get_position_from_map(chunk_map.graves[0].x, chunk_map.graves[0].y, None)
-                    }
-                    _ => {
-                        todo!()
-                    }
-                };
-                let at = (chunk_map.entrance.x, chunk_map.entrance.y);
+    for character in game_stage.0.characters() {
+        println!("🔥 character:{:?}", *character);
+        if let Some(ani) = characters
+            .iter()
+            .find(|&c| c.ani_type == character.ani_type)
+        {
+            let Some((x, y)) = get_character_cell(character, &chunk_map) else {
+                println!("🔥 Nowhere to spawn:{:?}", character.character_id);
+                continue;
+            };
+            let character_position = get_position_from_map(x, y, None);
 
-                // let at =
-                //     convert_map_to_screen(character_position.clone()).expect("Valid position");
-                // let position = get_position_from_map(at.0, at.1, None);
-
-                let character_bundle = build_character::<T>(
-                    &asset_server,
-                    &mut atlas_layouts,
-                    &mut library,
-                    ani.clone(),
-                    character.clone(),
-                    at,
-                );
-
-                let mut entity_commands = commands.spawn(character_bundle);
-
-                // Statics
-                entity_commands
-                    .insert(*character.ani_type())
-                    .insert(CharacterId(character.character_id().0.clone()))
-                    .insert((
-                        Action(*character.act()),
-                        Position {
-                            // xy: Vec2::new(position.translation.x, position.translation.y),
-                            xy: Vec2::new(
-                                character_position.translation.x,
-                                character_position.translation.y,
-                            ),
-                        },
-                        Health::new_full(100.0),
-                        Statbar::<Health> {
-                            color: Color::from(bevy::color::palettes::css::RED),
-                            empty_color: Color::from(bevy::color::palettes::css::BLACK),
-                            length: 32.0,
-                            thickness: 6.0,
-                            displacement: 32. * Vec2::Y,
-                            ..Default::default()
-                        },
-                    ));
+            spawn_character(
+                &mut commands,
+                &asset_server,
+                &mut atlas_layouts,
+                &mut library,
+                &mut ani_registry,
+                ani,
+                character,
+                character_position.translation.xy(),
+            );
+        }
+    }
+}
 
-                // Dynamics
-                get_fighter::<T>(&mut entity_commands);
-                get_looter::<T>(&mut entity_commands);
-                entity_commands.insert((get_thinker::<T>(), get_behavior::<T>()));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::map::MapPosition;
 
-                let character_id = entity_commands.id();
+    #[test]
+    fn test_get_spawn_cell() {
+        let chunk_map = ChunkMap {
+            walkables: vec![vec![true, false, true], vec![true, true, true]],
+            graves: vec![MapPosition { x: 0, y: 0 }, MapPosition { x: 2, y: 1 }],
+            ..default()
+        };
 
-                commands
-                    .spawn((
-                        Statbar::<Health> {
-                            color: Color::WHITE,
-                            empty_color: Color::BLACK,
-                            length: 500.0,
-                            thickness: 50.0,
-                            ..Default::default()
-                        },
-                        StatbarObserveEntity(character_id),
-                    ))
-                    .insert(SpatialBundle {
-                        transform: Transform::from_translation(-200. * Vec3::Y),
-                        ..Default::default()
-                    });
-            }
-        }
+        assert_eq!(get_spawn_cell("a2", &chunk_map), Some((0, 1)));
+        // Unwalkable or off the map, the closest grave
+        assert_eq!(get_spawn_cell("b1", &chunk_map), Some((0, 0)));
+        assert_eq!(get_spawn_cell("c8", &chunk_map), Some((2, 1)));
+        // Missing, the first one
+        assert_eq!(get_spawn_cell("", &chunk_map), Some((0, 0)));
+        assert_eq!(get_spawn_cell("a1", &ChunkMap::default()), None);
     }
 }
@@ -0,0 +1,220 @@
+use bevy::prelude::*;
+use big_brain::prelude::*;
+
+use crate::{
+    brains::{
+        fight::{Fighter, TargetAt},
+        loot::Looter,
+        thinker::{get_thinker, MAX_DISTANCE, MOVEMENT_SPEED},
+    },
+    characters::{
+        actions::{Act, Action},
+        effect::{speed_multiplier, StatusEffects},
+        entities::CharacterKind,
+    },
+    core::{map::get_map_from_position, position::Position, scene::ChunkMap},
+    entry::KeyBindings,
+    interactions::{damage::Death, pick::Selected, toggle::Interactable},
+};
+
+// Marker for the character driven by the player instead of the thinker.
+#[derive(Component, Debug)]
+pub struct DirectControl;
+
+// Same for every layout in `KeyBindings`.
+const TOGGLE_KEY: KeyCode = KeyCode::Tab;
+
+fn is_gamepad_pressed(
+    gamepads: &Gamepads,
+    gamepad_buttons: &ButtonInput<GamepadButton>,
+    button_type: GamepadButtonType,
+) -> bool {
+    gamepads
+        .iter()
+        .any(|gamepad| gamepad_buttons.pressed(GamepadButton::new(gamepad, button_type)))
+}
+
+#[allow(clippy::type_complexity)]
+pub fn toggle_direct_control_system(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut heroes: Query<
+        (
+            Entity,
+            Option<&DirectControl>,
+            &mut Action,
+            &mut TargetAt,
+            Option<&mut Fighter>,
+            Option<&mut Looter>,
+            &CharacterKind,
+            Option<&Selected>,
+        ),
+        Without<Death>,
+    >,
+) {
+    let is_select_pressed = gamepads.iter().any(|gamepad| {
+        gamepad_buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::Select))
+    });
+    if !keyboard_input.just_pressed(TOGGLE_KEY) && !is_select_pressed {
+        return;
+    }
+
+    // Selected hero, else the one already driven, else any
+    let picked = heroes
+        .iter()
+        .filter(|(.., kind, _)| **kind == CharacterKind::Human)
+        .max_by_key(|(_, direct_control, .., selected)| {
+            (selected.is_some(), direct_control.is_some())
+        })
+        .map(|(entity, ..)| entity);
+
+    for (entity, direct_control, mut action, mut target_at, fighter, looter, _, _) in
+        heroes.iter_mut()
+    {
+        // Picked one switch, the others go back to their thinker
+        let is_direct = match (Some(entity) == picked, direct_control.is_some()) {
+            (true, is_direct) => !is_direct,
+            (false, true) => false,
+            (false, false) => continue,
+        };
+
+        // Start over from idle either way
+        *action = Action(Act::Idle);
+        *target_at = TargetAt::default();
+        if let Some(mut fighter) = fighter {
+            fighter.is_fighting = false;
+        }
+        if let Some(mut looter) = looter {
+            looter.is_looting = false;
+        }
+
+        match is_direct {
+            false => {
+                println!("🤖 Thinker control");
+                commands
+                    .entity(entity)
+                    .remove::<DirectControl>()
+                    .insert(get_thinker(&CharacterKind::Human));
+            }
+            true => {
+                // Removing the builder also despawn the thinker.
+                println!("🎮 Direct control");
+                commands
+                    .entity(entity)
+                    .remove::<ThinkerBuilder>()
+                    .insert(DirectControl);
+            }
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn direct_control_system(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    chunk_map: Res<ChunkMap>,
+    mut characters: Query<
+        (
+            &mut Position,
+            &mut Action,
+            &mut TargetAt,
+            &Sprite,
+            Option<&StatusEffects>,
+        ),
+        (With<DirectControl>, Without<Death>),
+    >,
+    interactables: Query<(Entity, &Position), (With<Interactable>, Without<DirectControl>)>,
+) {
+    for (mut position, mut action, mut target_at, sprite, effects) in characters.iter_mut() {
+        // Direction from keyboard
+        let mut direction = Vec2::ZERO;
+        if keyboard_input.any_pressed(key_bindings.up().iter().copied()) {
+            direction.y += 1.;
+        }
+        if keyboard_input.any_pressed(key_bindings.down().iter().copied()) {
+            direction.y -= 1.;
+        }
+        if keyboard_input.any_pressed(key_bindings.left().iter().copied()) {
+            direction.x -= 1.;
+        }
+        if keyboard_input.any_pressed(key_bindings.right().iter().copied()) {
+            direction.x += 1.;
+        }
+
+        // Direction from gamepad
+        for gamepad in gamepads.iter() {
+            let x = gamepad_axes
+                .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+                .unwrap_or(0.);
+            let y = gamepad_axes
+                .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+                .unwrap_or(0.);
+            direction += Vec2::new(x, y);
+        }
+
+        let is_attack_pressed = keyboard_input.pressed(key_bindings.attack())
+            || is_gamepad_pressed(&gamepads, &gamepad_buttons, GamepadButtonType::South);
+        let is_open_pressed = keyboard_input.pressed(key_bindings.open())
+            || is_gamepad_pressed(&gamepads, &gamepad_buttons, GamepadButtonType::East);
+
+        if direction.length() > 0.1 {
+            let step = direction.normalize()
+                * MOVEMENT_SPEED
+                * speed_multiplier(effects)
+                * time.delta_seconds();
+            let next_xy = position.xy + step;
+
+            // Stay on walkable cells
+            let (x, y) = get_map_from_position(next_xy, None);
+            let is_walkable = chunk_map
+                .walkables
+                .get(y)
+                .and_then(|row| row.get(x))
+                .copied()
+                .unwrap_or(false);
+
+            if is_walkable {
+                position.xy = next_xy;
+            }
+
+            *action = Action(Act::Walk);
+        } else if is_attack_pressed {
+            // Hit whatever in front
+            let facing = if sprite.flip_x { -Vec2::X } else { Vec2::X };
+
+            target_at.entity = None;
+            target_at.last_position = Some(Position {
+                xy: position.xy + facing * MAX_DISTANCE,
+            });
+
+            *action = Action(Act::Attack);
+        } else if is_open_pressed {
+            let closest = interactables
+                .iter()
+                .map(|(entity, target_position)| {
+                    (
+                        entity,
+                        target_position,
+                        target_position.xy.distance(position.xy),
+                    )
+                })
+                .filter(|(_, _, distance)| *distance <= MAX_DISTANCE * 1.5)
+                .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b));
+
+            if let Some((entity, target_position, _)) = closest {
+                target_at.entity = Some(entity);
+                target_at.last_position = Some(*target_position);
+
+                *action = Action(Act::Open);
+            }
+        } else if action.0 != Act::Die {
+            *action = Action(Act::Idle);
+        }
+    }
+}
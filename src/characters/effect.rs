@@ -0,0 +1,364 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
+
+use crate::{
+    core::{
+        map::{convert_map_to_screen, get_map_from_position},
+        position::Position,
+        stage::GameStage,
+    },
+    interactions::{damage::Death, death::Inventory},
+};
+
+use super::{
+    actions::{Act, Action},
+    bar::{Health, HealthValue, StatRate},
+    entities::CharacterKind,
+};
+
+const ICON_SIZE: f32 = 3.;
+const HASTE_SPEED: f32 = 1.5;
+const HASTE_ATTACK: f32 = 1.25;
+const DEFAULT_SHIELD: f32 = 0.5;
+const POTION: &str = "potion";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum EffectKind {
+    Poison,
+    Regeneration,
+    Stun,
+    Haste,
+    Shield,
+}
+
+impl EffectKind {
+    // Poison pile up, the rest refresh.
+    fn max_stacks(&self) -> usize {
+        match self {
+            EffectKind::Poison => 3,
+            _ => 1,
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            EffectKind::Poison => Color::srgb(0.3, 0.8, 0.2),
+            EffectKind::Regeneration => Color::srgb(1.0, 0.4, 0.6),
+            EffectKind::Stun => Color::srgb(1.0, 0.9, 0.2),
+            EffectKind::Haste => Color::srgb(0.3, 0.9, 1.0),
+            EffectKind::Shield => Color::srgb(0.3, 0.4, 1.0),
+        }
+    }
+}
+
+// `power` is health per second for poison and regeneration, blocked ratio for shield.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StatusEffect {
+    pub kind: EffectKind,
+    pub duration: f32,
+    #[serde(default)]
+    pub power: f32,
+}
+
+// Cell that keep applying an effect to whoever stand on it, from the stage.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct EffectTile {
+    pub cell: String,
+    pub effect: StatusEffect,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct EffectEvent {
+    pub target: Entity,
+    pub effect: StatusEffect,
+}
+
+#[derive(Component, Debug, Default, Clone)]
+pub struct StatusEffects(pub Vec<StatusEffect>);
+
+impl StatusEffects {
+    pub fn has(&self, kind: EffectKind) -> bool {
+        self.0.iter().any(|effect| effect.kind == kind)
+    }
+
+    pub fn add(&mut self, effect: StatusEffect) {
+        let stacks = self.0.iter().filter(|e| e.kind == effect.kind).count();
+        if stacks < effect.kind.max_stacks() {
+            self.0.push(effect);
+            return;
+        }
+
+        // Full, refresh the one closest to run out.
+        if let Some(oldest) = self
+            .0
+            .iter_mut()
+            .filter(|e| e.kind == effect.kind)
+            .min_by(|a, b| a.duration.total_cmp(&b.duration))
+        {
+            oldest.duration = oldest.duration.max(effect.duration);
+            oldest.power = oldest.power.max(effect.power);
+        }
+    }
+
+    pub fn speed_multiplier(&self) -> f32 {
+        match (self.has(EffectKind::Stun), self.has(EffectKind::Haste)) {
+            (true, _) => 0.,
+            (false, true) => HASTE_SPEED,
+            (false, false) => 1.,
+        }
+    }
+
+    pub fn attack_multiplier(&self) -> f32 {
+        match (self.has(EffectKind::Stun), self.has(EffectKind::Haste)) {
+            (true, _) => 0.,
+            (false, true) => HASTE_ATTACK,
+            (false, false) => 1.,
+        }
+    }
+
+    pub fn incoming_multiplier(&self) -> f32 {
+        let blocked = self
+            .0
+            .iter()
+            .filter(|effect| effect.kind == EffectKind::Shield)
+            .map(|effect| match effect.power > 0. {
+                true => effect.power,
+                false => DEFAULT_SHIELD,
+            })
+            .fold(0., f32::max);
+
+        1. - blocked.clamp(0., 1.)
+    }
+
+    pub fn health_per_second(&self) -> f32 {
+        self.0
+            .iter()
+            .map(|effect| match effect.kind {
+                EffectKind::Regeneration => effect.power,
+                EffectKind::Poison => -effect.power,
+                _ => 0.,
+            })
+            .sum()
+    }
+}
+
+// Shorthand for systems that move or hit, no effects means no change.
+pub fn speed_multiplier(effects: Option<&StatusEffects>) -> f32 {
+    effects.map_or(1., StatusEffects::speed_multiplier)
+}
+
+#[derive(Component)]
+pub struct EffectIcon;
+
+pub fn apply_effect_events(
+    mut effect_events: EventReader<EffectEvent>,
+    mut targets: Query<&mut StatusEffects>,
+) {
+    for EffectEvent { target, effect } in effect_events.read() {
+        if let Ok(mut effects) = targets.get_mut(*target) {
+            println!("✨ {}:{:?}", effect.kind, target);
+            effects.add(effect.clone());
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_status_effects(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut characters: Query<(
+        Entity,
+        &mut StatusEffects,
+        &mut StatRate<HealthValue>,
+        &Health,
+        &mut Action,
+    )>,
+) {
+    for (entity, mut effects, mut health_rate, health, mut action) in characters.iter_mut() {
+        if effects.0.is_empty() {
+            continue;
+        }
+
+        // Ticking alone shouldn't redraw the icons.
+        let count = effects.0.len();
+        let ticking = effects.bypass_change_detection();
+        for effect in ticking.0.iter_mut() {
+            effect.duration -= time.delta_seconds();
+        }
+        ticking.0.retain(|effect| effect.duration > 0.);
+        if ticking.0.len() != count {
+            effects.set_changed();
+        }
+
+        health_rate.per_second = effects.health_per_second();
+
+        // Poisoned to death
+        if health.value <= 0. && health_rate.per_second < 0. {
+            *action = Action(Act::Die);
+            commands.entity(entity).insert(Death);
+        }
+    }
+}
+
+pub fn update_effect_tiles(
+    game_stage: Res<GameStage>,
+    characters: Query<(Entity, &Position, &StatusEffects)>,
+    mut effect_events: EventWriter<EffectEvent>,
+) {
+    for tile in game_stage.0.tiles.iter() {
+        let Some(at) = convert_map_to_screen(tile.cell.clone()) else {
+            continue;
+        };
+
+        for (entity, position, effects) in characters.iter() {
+            if get_map_from_position(position.xy, None) == at && !effects.has(tile.effect.kind) {
+                effect_events.send(EffectEvent {
+                    target: entity,
+                    effect: tile.effect.clone(),
+                });
+            }
+        }
+    }
+}
+
+// "when low(<50%) health and has potion, drink potion"
+pub fn drink_potion(
+    mut inventory: ResMut<Inventory>,
+    heroes: Query<(Entity, &Health, &CharacterKind, &StatusEffects), With<Position>>,
+    mut effect_events: EventWriter<EffectEvent>,
+) {
+    for (entity, health, kind, effects) in heroes.iter() {
+        if *kind != CharacterKind::Human
+            || health.value >= health.max * 0.5
+            || effects.has(EffectKind::Regeneration)
+        {
+            continue;
+        }
+
+        let Some(index) = inventory.0.iter().position(|item| item == POTION) else {
+            return;
+        };
+
+        println!("🧪 Drink potion:{:?}", entity);
+        inventory.0.remove(index);
+        effect_events.send(EffectEvent {
+            target: entity,
+            effect: StatusEffect {
+                kind: EffectKind::Regeneration,
+                duration: 5.,
+                power: 6.,
+            },
+        });
+    }
+}
+
+// Small colored squares over the head, one per effect.
+pub fn update_effect_icons(
+    mut commands: Commands,
+    characters: Query<(Entity, &StatusEffects), Changed<StatusEffects>>,
+    icons: Query<(Entity, &Parent), With<EffectIcon>>,
+    mut removed: RemovedComponents<StatusEffects>,
+) {
+    // Redrawn or dead, drop the old ones.
+    let removed = removed.read().collect::<Vec<_>>();
+    for (icon, parent) in icons.iter() {
+        if characters.contains(parent.get()) || removed.contains(&parent.get()) {
+            commands.entity(icon).despawn_recursive();
+        }
+    }
+
+    for (entity, effects) in characters.iter() {
+        // Local to the character, scaled with it.
+        let count = effects.0.len() as f32;
+        commands.entity(entity).with_children(|parent| {
+            for (i, effect) in effects.0.iter().enumerate() {
+                let x = (i as f32 - (count - 1.) / 2.) * (ICON_SIZE + 1.);
+                parent.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: effect.kind.color(),
+                            custom_size: Some(Vec2::splat(ICON_SIZE)),
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(x, 22., 1.),
+                        ..default()
+                    },
+                    EffectIcon,
+                ));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn effect(kind: EffectKind, duration: f32, power: f32) -> StatusEffect {
+        StatusEffect {
+            kind,
+            duration,
+            power,
+        }
+    }
+
+    #[test]
+    fn test_poison_stacks_up_to_3() {
+        let mut effects = StatusEffects::default();
+        for duration in [1., 2., 3., 4.] {
+            effects.add(effect(EffectKind::Poison, duration, 2.));
+        }
+
+        // The 4th refresh the one closest to run out
+        assert_eq!(effects.0.len(), 3);
+        let mut durations = effects.0.iter().map(|e| e.duration).collect::<Vec<_>>();
+        durations.sort_by(f32::total_cmp);
+        assert_eq!(durations, vec![2., 3., 4.]);
+        assert_eq!(effects.health_per_second(), -6.);
+    }
+
+    #[test]
+    fn test_others_refresh() {
+        let mut effects = StatusEffects::default();
+        effects.add(effect(EffectKind::Haste, 3., 0.));
+        effects.add(effect(EffectKind::Haste, 1., 0.));
+        effects.add(effect(EffectKind::Regeneration, 2., 5.));
+
+        assert_eq!(effects.0.len(), 2);
+        assert_eq!(effects.0[0].duration, 3.);
+        assert_eq!(effects.health_per_second(), 5.);
+    }
+
+    #[test]
+    fn test_shield() {
+        let mut effects = StatusEffects::default();
+        assert_eq!(effects.incoming_multiplier(), 1.);
+
+        // No power, default block
+        effects.add(effect(EffectKind::Shield, 5., 0.));
+        assert_eq!(effects.incoming_multiplier(), 1. - DEFAULT_SHIELD);
+
+        let effects = StatusEffects(vec![effect(EffectKind::Shield, 5., 2.)]);
+        assert_eq!(effects.incoming_multiplier(), 0.);
+    }
+
+    #[test]
+    fn test_multipliers() {
+        let mut effects = StatusEffects::default();
+        assert_eq!(effects.speed_multiplier(), 1.);
+        assert_eq!(effects.attack_multiplier(), 1.);
+
+        effects.add(effect(EffectKind::Haste, 3., 0.));
+        assert_eq!(effects.speed_multiplier(), HASTE_SPEED);
+        assert_eq!(effects.attack_multiplier(), HASTE_ATTACK);
+
+        // Stun win over haste
+        effects.add(effect(EffectKind::Stun, 1., 0.));
+        assert_eq!(effects.speed_multiplier(), 0.);
+        assert_eq!(speed_multiplier(Some(&effects)), 0.);
+        assert_eq!(speed_multiplier(None), 1.);
+    }
+}
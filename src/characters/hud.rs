@@ -0,0 +1,128 @@
+use bevy::{color::palettes::css::RED, prelude::*};
+
+use crate::{
+    core::{layer::SpriteLayer, objective::ObjectiveHud},
+    entry::{game::OnGameScreen, TEXT_COLOR},
+};
+
+use super::{
+    bar::Health,
+    entities::{CharacterId, CharacterKind},
+};
+
+const HUD_BAR_WIDTH: f32 = 96.;
+const FALLEN_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+
+// Name of the hero this row is about.
+#[derive(Component)]
+pub struct HeroHudName(pub Entity);
+
+// Filled part of the health bar of the hero.
+#[derive(Component)]
+pub struct HeroHudFill(pub Entity);
+
+// Top left panel: objectives then a row per hero.
+pub fn spawn_hud(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    heroes: Query<(Entity, &CharacterId, &CharacterKind)>,
+) {
+    let font = asset_server.load("PixelOperator-Bold.ttf");
+    let text_style = TextStyle {
+        font,
+        font_size: 16.0,
+        color: TEXT_COLOR,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(4.0),
+                    top: Val::Px(4.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                ..default()
+            },
+            SpriteLayer::Ui,
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            // Objectives
+            parent.spawn((
+                TextBundle::from_section("", text_style.clone()),
+                ObjectiveHud,
+            ));
+
+            // Heroes
+            for (entity, character_id, _) in heroes
+                .iter()
+                .filter(|(.., kind)| **kind == CharacterKind::Human)
+            {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(6.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(character_id.0.clone(), text_style.clone()),
+                            HeroHudName(entity),
+                        ));
+
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    width: Val::Px(HUD_BAR_WIDTH),
+                                    height: Val::Px(8.0),
+                                    ..default()
+                                },
+                                background_color: Color::BLACK.into(),
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    NodeBundle {
+                                        style: Style {
+                                            width: Val::Percent(100.0),
+                                            height: Val::Percent(100.0),
+                                            ..default()
+                                        },
+                                        background_color: RED.into(),
+                                        ..default()
+                                    },
+                                    HeroHudFill(entity),
+                                ));
+                            });
+                    });
+            }
+        });
+}
+
+pub fn update_hero_hud(
+    heroes: Query<&Health>,
+    mut fills: Query<(&HeroHudFill, &mut Style)>,
+    mut names: Query<(&HeroHudName, &mut Text)>,
+) {
+    for (HeroHudFill(hero), mut style) in fills.iter_mut() {
+        let ratio = heroes
+            .get(*hero)
+            .map(|health| health.value / health.max)
+            .unwrap_or(0.);
+        style.width = Val::Percent(ratio * 100.);
+    }
+
+    // Gone for good
+    for (HeroHudName(hero), mut text) in names.iter_mut() {
+        if heroes.get(*hero).is_err() {
+            text.sections[0].style.color = FALLEN_COLOR;
+        }
+    }
+}
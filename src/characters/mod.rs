@@ -1,5 +1,9 @@
 pub mod actions;
 pub mod bar;
 pub mod builder;
+pub mod control;
+pub mod effect;
 pub mod entities;
+pub mod hud;
+pub mod stats;
 pub mod update;
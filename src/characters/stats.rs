@@ -0,0 +1,100 @@
+use bevy::{
+    color::palettes::css::{GOLD, MEDIUM_PURPLE},
+    ecs::system::EntityCommands,
+    prelude::*,
+};
+
+use crate::{
+    core::{
+        faction::{FactionTable, Relation},
+        stage::CharacterDef,
+    },
+    interactions::{
+        damage::{DamageEvent, HitEvent},
+        death::DeathEvent,
+    },
+};
+
+use super::{
+    actions::{Act, Action},
+    bar::{get_stat_bar, Morale, MoraleValue, Stamina, StaminaValue, StatRate},
+    entities::CharacterKind,
+};
+
+// Stamina per second
+const RUN_COST: f32 = 4.;
+const REST_GAIN: f32 = 10.;
+// Stamina per swing
+const ATTACK_COST: f32 = 8.;
+
+// Morale per damage taken
+const HIT_MORALE: f32 = 0.5;
+const ALLY_DEATH_MORALE: f32 = 30.;
+const MORALE_RECOVERY: f32 = 1.;
+
+// Only for those with `stamina` or `morale` in the stage, bars below health.
+pub fn get_stats(character_def: &CharacterDef, entity_commands: &mut EntityCommands) {
+    if character_def.stamina > 0 {
+        entity_commands.insert((
+            Stamina::new_full(character_def.stamina as f32),
+            StatRate::<StaminaValue>::default(),
+            get_stat_bar::<StaminaValue>(&character_def.kind, GOLD.into(), 1),
+        ));
+    }
+
+    if character_def.morale > 0 {
+        entity_commands.insert((
+            Morale::new_full(character_def.morale as f32),
+            StatRate::<MoraleValue>::new(MORALE_RECOVERY),
+            get_stat_bar::<MoraleValue>(&character_def.kind, MEDIUM_PURPLE.into(), 2),
+        ));
+    }
+}
+
+// Spent walking, back while idle.
+pub fn update_stamina_rate(mut characters: Query<(&Action, &mut StatRate<StaminaValue>)>) {
+    for (action, mut stamina_rate) in characters.iter_mut() {
+        stamina_rate.per_second = match action.0 {
+            Act::Walk => -RUN_COST,
+            Act::Idle => REST_GAIN,
+            _ => 0.,
+        };
+    }
+}
+
+pub fn spend_stamina_on_attack(
+    mut damage_events: EventReader<DamageEvent>,
+    mut characters: Query<&mut Stamina>,
+) {
+    for DamageEvent(damage) in damage_events.read() {
+        let Some(attacker) = damage.attacker else {
+            continue;
+        };
+        if let Ok(mut stamina) = characters.get_mut(attacker) {
+            *stamina -= ATTACK_COST;
+        }
+    }
+}
+
+// Hurt by taking hits and seeing allies fall.
+pub fn update_morale(
+    factions: Res<FactionTable>,
+    mut hit_events: EventReader<HitEvent>,
+    mut death_events: EventReader<DeathEvent>,
+    mut characters: Query<(Entity, &CharacterKind, &mut Morale)>,
+) {
+    for hit in hit_events.read() {
+        if let Ok((.., mut morale)) = characters.get_mut(hit.target) {
+            *morale -= hit.amount * HIT_MORALE;
+        }
+    }
+
+    for death in death_events.read() {
+        for (entity, kind, mut morale) in characters.iter_mut() {
+            if entity != death.entity && factions.relation(kind, &death.kind) == Relation::Allied {
+                println!("😨 Lost {:?}:{:?}", death.character_id, entity);
+                *morale -= ALLY_DEATH_MORALE;
+            }
+        }
+    }
+}
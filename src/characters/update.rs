@@ -1,14 +1,18 @@
 use crate::{
-    animations::{entities::AniType, utils::get_animation_name},
-    brains::fight::TargetAt,
+    animations::{
+        build::AniRegistry,
+        entities::{Ani, AniEventKind, AniType},
+        utils::get_animation_name,
+    },
+    brains::{fight::TargetAt, thinker::MAX_DISTANCE},
     characters::{
         actions::{Act, Action},
-        entities::CharacterId,
+        effect::{EffectKind, StatusEffects},
     },
     core::{
-        chest::ChestId,
-        position::Position,
-        stage::{CharacterInfo, GameStage, StageInfo},
+        position::{Position, PreviousPosition},
+        stage::CharacterDef,
+        tick::get_interpolated_xy,
     },
     interactions::{
         damage::{Damage, DamageEvent},
@@ -18,159 +22,240 @@ use crate::{
 use bevy::prelude::*;
 use bevy_spritesheet_animation::prelude::*;
 
-use super::{actions::AniAction, entities::CharacterKind};
-
 #[allow(clippy::complexity)]
-pub fn update_character<T>(
-    game_stage: Res<GameStage>,
-    mut characters: Query<
-        (
-            &CharacterId,
-            &AniType,
-            &mut Position,
-            &mut Transform,
-            &mut Sprite,
-            &mut SpritesheetAnimation,
-            &mut Action,
-            &mut AniAction,
-            &mut TargetAt,
-            &T,
-        ),
-        With<T>,
-    >,
-    library: Res<AnimationLibrary>,
+pub fn update_character(
+    mut characters: Query<(
+        &AniType,
+        &Position,
+        Option<&PreviousPosition>,
+        &mut Transform,
+        &mut Sprite,
+        &mut SpritesheetAnimation,
+        &Action,
+        &TargetAt,
+        &AttackCooldown,
+    )>,
+    ani_registry: Res<AniRegistry>,
+    fixed_time: Res<Time<Fixed>>,
+) {
+    for (
+        ani_type,
+        character_position,
+        previous_position,
+        mut character_transform,
+        mut sprite,
+        mut animation,
+        action,
+        actor_target_at,
+        attack_cooldown,
+    ) in characters.iter_mut()
+    {
+        // Position, smoothed between ticks
+        let xy = get_interpolated_xy(character_position, previous_position, &fixed_time);
+        match action.0 {
+            Act::Walk => {
+                // Look direction
+                if character_transform.translation.x != xy.x {
+                    sprite.flip_x = character_transform.translation.x > xy.x;
+                }
+
+                character_transform.translation.x = xy.x;
+                character_transform.translation.y = xy.y;
+            }
+            Act::Hurt => {
+                // Knockback
+                character_transform.translation.x = xy.x;
+                character_transform.translation.y = xy.y;
+            }
+            Act::Attack | Act::Open => {
+                // Look direction
+                if let Some(actor_target_at_position) = actor_target_at.last_position {
+                    sprite.flip_x =
+                        character_transform.translation.x > actor_target_at_position.xy.x;
+                };
+            }
+            _ => (),
+        }
+
+        // Action, catch a breath between attacks
+        let act = match action.0 {
+            Act::Attack if attack_cooldown.remaining > 0. => Act::Idle,
+            act => act,
+        };
+        let animation_name = get_animation_name(ani_type, act);
+
+        if let Some(animation_id) = ani_registry.get(animation_name) {
+            if animation.animation_id != animation_id {
+                animation.switch(animation_id);
+            }
+        }
+    }
+}
+
+// Time left before next attack, from `attack_cooldown` in stage.
+#[derive(Component, Debug, Default)]
+pub struct AttackCooldown {
+    pub duration: f32,
+    pub remaining: f32,
+}
+
+impl AttackCooldown {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            duration,
+            remaining: 0.,
+        }
+    }
+}
+
+pub fn update_attack_cooldown(time: Res<Time>, mut query: Query<&mut AttackCooldown>) {
+    for mut attack_cooldown in query.iter_mut() {
+        attack_cooldown.remaining = (attack_cooldown.remaining - time.delta_seconds()).max(0.);
+    }
+}
+
+// Where hits and toggles land in the attack and open clips, in seconds, from char.ani.json markers.
+#[derive(Debug, Default, Clone)]
+pub struct SwingTiming {
+    pub duration: f32,
+    pub events: Vec<(f32, AniEventKind)>,
+}
+
+#[derive(Component, Debug, Default, Clone)]
+pub struct SwingTimings {
+    pub attack: SwingTiming,
+    pub open: SwingTiming,
+}
+
+impl SwingTimings {
+    pub fn new(ani: &Ani, clip_fps: u32) -> Self {
+        let get_timing = |act: Act| {
+            ani.animations
+                .iter()
+                .find(|animation| animation.action_name == act.to_string())
+                .map(|animation| SwingTiming {
+                    duration: animation.count as f32 / clip_fps as f32,
+                    events: animation
+                        .get_events()
+                        .iter()
+                        .map(|event| (event.frame as f32 / clip_fps as f32, event.name))
+                        .collect(),
+                })
+                .unwrap_or_default()
+        };
+
+        Self {
+            attack: get_timing(Act::Attack),
+            open: get_timing(Act::Open),
+        }
+    }
+}
+
+// How far into the current attack or open, on the tick.
+#[derive(Component, Debug, Default)]
+pub struct Swing {
+    pub act: Act,
+    pub elapsed: f32,
+}
+
+// Fire hits and toggles on the tick, at the time their markers sit in the clip.
+#[allow(clippy::type_complexity)]
+pub fn update_swing(
+    time: Res<Time>,
+    mut characters: Query<(
+        Entity,
+        &Position,
+        &Action,
+        &TargetAt,
+        &mut AttackCooldown,
+        &mut Swing,
+        &SwingTimings,
+        &CharacterDef,
+        Option<&StatusEffects>,
+    )>,
     mut damage_events: EventWriter<DamageEvent>,
     mut toggle_events: EventWriter<ToggleEvent>,
-) where
-    T: CharacterInfo + 'static,
-{
-    if let Some(character_iter) = game_stage.0.get_characters_iter_by_type::<T>() {
-        for character in character_iter {
-            for (
-                character_id,
-                ani_type,
-                character_position,
-                mut character_transform,
-                mut sprite,
-                mut animation,
-                action,
-                mut ani_action,
-                actor_target_at,
-                character_info,
-            ) in characters.iter_mut()
+) {
+    for (
+        entity,
+        actor_position,
+        action,
+        actor_target_at,
+        mut attack_cooldown,
+        mut swing,
+        swing_timings,
+        character_def,
+        effects,
+    ) in characters.iter_mut()
+    {
+        // Start over on a new act
+        if swing.act != action.0 {
+            swing.act = action.0;
+            swing.elapsed = 0.;
+        }
+
+        // Stunned, the swing hold until it wear off
+        if effects.is_some_and(|effects| effects.has(EffectKind::Stun)) {
+            continue;
+        }
+
+        let swing_timing = match action.0 {
+            Act::Attack if attack_cooldown.remaining <= 0. => &swing_timings.attack,
+            Act::Open => &swing_timings.open,
+            _ => continue,
+        };
+
+        let from = swing.elapsed;
+        swing.elapsed += time.delta_seconds();
+
+        if let Some(actor_target_at_position) = actor_target_at.last_position {
+            let delta = actor_target_at_position.xy - actor_position.xy;
+
+            for (_, event_kind) in swing_timing
+                .events
+                .iter()
+                .filter(|(at, _)| from <= *at && *at < swing.elapsed)
             {
-                if character.character_id() == character_id {
-                    // Position
-                    match action.0 {
-                        Act::Walk => {
-                            // Look direction
-                            sprite.flip_x =
-                                character_transform.translation.x > character_position.xy.x;
-
-                            // Snap
-                            character_transform.translation.x = character_position.xy.x;
-                            character_transform.translation.y = character_position.xy.y;
-
-                            *ani_action = AniAction { act: action.0 };
-                        }
-                        Act::Attack => {
-                            // Look direction
-                            if let Some(actor_target_at_position) = actor_target_at.last_position {
-                                sprite.flip_x = character_transform.translation.x
-                                    > actor_target_at_position.xy.x;
-
-                                // TODO: use total frame /2
-                                // TOFIX: damage position max to radius
-                                if animation.progress.frame == 3 && ani_action.act != Act::Attack {
-                                    // Damage
-                                    let actor_position = character_position;
-                                    let delta = actor_target_at_position.xy - actor_position.xy;
-                                    let _distance = delta.length();
-                                    let direction = delta.normalize_or_zero();
-                                    let damage_position = actor_position.xy + delta;
-
-                                    let damage = Damage {
-                                        by: *character_info.kind(),
-                                        position: damage_position,
-                                        power: character_info.attack() as f32,
-                                        radius: 48.,
-                                        direction,
-                                        duration: 0.5,
-                                    };
-
-                                    damage_events.send(DamageEvent(damage));
-                                    *ani_action = AniAction { act: action.0 };
-                                };
-
-                                // This weird
-                                if animation.progress.frame > 5 {
-                                    *ani_action = AniAction { act: Act::Idle };
-                                }
-                            };
-                        }
-                        Act::Die => {
-                            // TODO: play once by value from char.json
-                            let total_frame = match character_info.kind() {
-                                CharacterKind::Human => 12,
-                                CharacterKind::Monster => 9,
-                                CharacterKind::Animal => todo!(),
-                            };
+                match (action.0, event_kind) {
+                    (Act::Attack, AniEventKind::Hit) => {
+                        let multiplier = effects.map_or(1., |effects| effects.attack_multiplier());
+                        let damage = Damage {
+                            by: character_def.kind,
+                            attacker: Some(entity),
+                            // No further than the attacker can reach
+                            position: actor_position.xy + delta.clamp_length_max(MAX_DISTANCE),
+                            power: character_def.attack as f32 * multiplier,
+                            radius: 48.,
+                            direction: delta.normalize_or_zero(),
+                            duration: 0.5,
+                            effects: character_def.on_hit.clone(),
+                        };
 
-                            if animation.progress.repetition >= 1
-                                && animation.progress.frame == total_frame
-                            {
-                                animation.playing = false;
-                            } else {
-                                *ani_action = AniAction { act: action.0 };
-                            }
-                        }
-                        Act::Open => {
-                            // Look direction
-                            if let Some(actor_target_at_position) = actor_target_at.last_position {
-                                sprite.flip_x = character_transform.translation.x
-                                    > actor_target_at_position.xy.x;
-
-                                // TODO: use total frame /2
-                                if animation.progress.frame == 3 && ani_action.act != Act::Open {
-                                    // Open
-                                    let actor_position = character_position;
-                                    let delta = actor_target_at_position.xy - actor_position.xy;
-                                    let _distance = delta.length();
-                                    // let direction = delta.normalize_or_zero();
-                                    let toggle_position = actor_position.xy + delta;
-
-                                    let toggle = Toggle {
-                                        by: *character_info.kind(),
-                                        position: toggle_position,
-                                        // TOFIX
-                                        // target: ChestId(actor_target_at.id),
-                                        target: ChestId("chest_0".to_owned()),
-                                    };
-
-                                    println!("💥 ToggleEvent:{:?}", toggle);
-                                    toggle_events.send(ToggleEvent(toggle));
-                                    *ani_action = AniAction { act: action.0 };
-                                };
-
-                                // This weird
-                                if animation.progress.frame > 5 {
-                                    *ani_action = AniAction { act: Act::Idle };
-                                }
-                            };
-                        }
-                        _ => (),
+                        damage_events.send(DamageEvent(damage));
                     }
+                    (Act::Open, AniEventKind::Toggle) => {
+                        if let Some(target) = actor_target_at.entity {
+                            let toggle = Toggle {
+                                by: character_def.kind,
+                                position: actor_position.xy + delta,
+                                target,
+                            };
 
-                    // Action
-                    let animation_name = get_animation_name(ani_type, action.0);
-
-                    if let Some(animation_id) = library.animation_with_name(animation_name) {
-                        if animation.animation_id != animation_id {
-                            animation.switch(animation_id);
+                            println!("💥 ToggleEvent:{:?}", toggle);
+                            toggle_events.send(ToggleEvent(toggle));
                         }
                     }
+                    _ => (),
                 }
             }
         }
+
+        // Rest after each swing
+        if swing.elapsed >= swing_timing.duration {
+            swing.elapsed = 0.;
+            if action.0 == Act::Attack {
+                attack_cooldown.remaining = attack_cooldown.duration;
+            }
+        }
     }
 }
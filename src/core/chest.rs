@@ -1,6 +1,7 @@
 use bevy::{prelude::*, utils::HashMap};
 use bevy_spritesheet_animation::prelude::*;
 
+use crate::animations::build::AniRegistry;
 use crate::brains::loot::Looted;
 
 #[derive(Resource, Default, Debug)]
@@ -26,7 +27,7 @@ pub enum ChestState {
 #[allow(clippy::type_complexity)]
 pub fn update_chest(
     mut commands: Commands,
-    library: Res<AnimationLibrary>,
+    ani_registry: Res<AniRegistry>,
     mut chest_query: Query<
         (&ChestId, &mut SpritesheetAnimation, Entity),
         (With<Chest>, Without<Looted>),
@@ -36,7 +37,7 @@ pub fn update_chest(
     for (chest_id, mut animation, entity) in chest_query.iter_mut() {
         if let Some(chest) = chests.0.get(&chest_id.0) {
             if chest.status == ChestState::Open {
-                if let Some(open_animation_id) = library.animation_with_name("chest_open") {
+                if let Some(open_animation_id) = ani_registry.get("chest_open") {
                     animation.switch(open_animation_id);
                 }
 
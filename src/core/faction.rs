@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::characters::entities::CharacterKind;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Relation {
+    Hostile,
+    Neutral,
+    Allied,
+}
+
+type FactionRow = (CharacterKind, CharacterKind, Relation);
+
+// How each kind feel about the others, e.g. `[human, monster, hostile]`.
+// Relations go both ways, unlisted kinds are allied to their own and neutral to the rest.
+// Rows from the stage go over the defaults, so humans and monsters stay hostile unless listed.
+#[derive(Resource, Deserialize, Debug, Clone)]
+#[serde(from = "Vec<FactionRow>")]
+pub struct FactionTable(pub Vec<FactionRow>);
+
+impl Default for FactionTable {
+    fn default() -> Self {
+        Self(vec![(
+            CharacterKind::Human,
+            CharacterKind::Monster,
+            Relation::Hostile,
+        )])
+    }
+}
+
+impl From<Vec<FactionRow>> for FactionTable {
+    fn from(rows: Vec<FactionRow>) -> Self {
+        let mut factions = Self::default();
+        for row in rows {
+            factions.set(row);
+        }
+        factions
+    }
+}
+
+impl FactionTable {
+    // Replace the relation of this pair, whichever order it was listed in.
+    pub fn set(&mut self, (a, b, relation): FactionRow) {
+        self.0
+            .retain(|(x, y, _)| !((*x == a && *y == b) || (*x == b && *y == a)));
+        self.0.push((a, b, relation));
+    }
+
+    pub fn relation(&self, a: &CharacterKind, b: &CharacterKind) -> Relation {
+        self.0
+            .iter()
+            .find(|(x, y, _)| (x == a && y == b) || (x == b && y == a))
+            .map(|(.., relation)| *relation)
+            .unwrap_or(if a == b {
+                Relation::Allied
+            } else {
+                Relation::Neutral
+            })
+    }
+
+    pub fn is_hostile(&self, a: &CharacterKind, b: &CharacterKind) -> bool {
+        self.relation(a, b) == Relation::Hostile
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_relations() {
+        let factions = FactionTable::default();
+
+        assert!(factions.is_hostile(&CharacterKind::Human, &CharacterKind::Monster));
+        assert!(factions.is_hostile(&CharacterKind::Monster, &CharacterKind::Human));
+        assert_eq!(
+            factions.relation(&CharacterKind::Animal, &CharacterKind::Human),
+            Relation::Neutral
+        );
+        assert_eq!(
+            factions.relation(&CharacterKind::Monster, &CharacterKind::Monster),
+            Relation::Allied
+        );
+    }
+
+    #[test]
+    fn test_listed_relations_win() {
+        let factions: FactionTable = serde_yaml::from_str(
+            "
+- [monster, monster, hostile]
+- [animal, human, allied]
+",
+        )
+        .unwrap();
+
+        assert!(factions.is_hostile(&CharacterKind::Monster, &CharacterKind::Monster));
+        assert_eq!(
+            factions.relation(&CharacterKind::Human, &CharacterKind::Animal),
+            Relation::Allied
+        );
+        // Defaults are kept
+        assert!(factions.is_hostile(&CharacterKind::Human, &CharacterKind::Monster));
+    }
+
+    #[test]
+    fn test_listed_relations_override_defaults() {
+        let factions: FactionTable = serde_yaml::from_str("- [monster, human, neutral]").unwrap();
+
+        assert_eq!(factions.0.len(), 1);
+        assert_eq!(
+            factions.relation(&CharacterKind::Human, &CharacterKind::Monster),
+            Relation::Neutral
+        );
+    }
+}
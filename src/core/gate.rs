@@ -1,7 +1,13 @@
 use bevy::{prelude::*, utils::HashMap};
 use bevy_spritesheet_animation::prelude::*;
 
-use super::scene::Decor;
+use crate::animations::build::AniRegistry;
+
+use super::{
+    map::MapPosition,
+    objective::ObjectiveTracker,
+    scene::{ChunkMap, Decor},
+};
 
 #[derive(Resource, Default, Debug)]
 pub struct Gates(pub HashMap<String, Gate>);
@@ -12,6 +18,7 @@ pub struct Gate {
     // TODO
     #[allow(dead_code)]
     pub key: Option<String>,
+    pub at: MapPosition,
 }
 
 #[derive(Component, Debug)]
@@ -23,19 +30,54 @@ pub enum GateState {
     Open,
 }
 
+#[derive(Component)]
+pub struct Opened;
+
+impl Gates {
+    pub fn open_at(&mut self, at: &MapPosition) {
+        if let Some(gate) = self.0.values_mut().find(|gate| gate.at == *at) {
+            gate.status = GateState::Open;
+        }
+    }
+}
+
 #[allow(clippy::type_complexity)]
 pub fn update_gate(
-    library: Res<AnimationLibrary>,
-    mut gate: Query<(&GateId, &mut SpritesheetAnimation), With<Decor>>,
+    mut commands: Commands,
+    ani_registry: Res<AniRegistry>,
+    mut gate: Query<(&GateId, &mut SpritesheetAnimation, Entity), (With<Decor>, Without<Opened>)>,
     gates: Res<Gates>,
+    mut chunk_map: ResMut<ChunkMap>,
 ) {
-    for (gate_id, mut animation) in gate.iter_mut() {
+    for (gate_id, mut animation, entity) in gate.iter_mut() {
         if let Some(gate) = gates.0.get(&gate_id.0) {
             if gate.status == GateState::Open {
-                if let Some(open_animation_id) = library.animation_with_name("gate_open") {
+                if let Some(open_animation_id) = ani_registry.get("gate_open") {
                     animation.switch(open_animation_id);
                 }
+
+                // Walk through
+                chunk_map.walkables[gate.at.y][gate.at.x] = true;
+
+                println!("🚪 Opened!");
+                commands.entity(entity).insert(Opened);
             }
         }
     }
 }
+
+pub fn open_entrance_gate(chunk_map: Res<ChunkMap>, mut gates: ResMut<Gates>) {
+    if let Some(entrance_gate) = &chunk_map.entrance_gate {
+        gates.open_at(entrance_gate);
+    }
+}
+
+pub fn open_exit_gate(
+    chunk_map: Res<ChunkMap>,
+    objective_tracker: Res<ObjectiveTracker>,
+    mut gates: ResMut<Gates>,
+) {
+    if let (true, Some(exit_gate)) = (objective_tracker.is_ready_to_exit(), &chunk_map.exit_gate) {
+        gates.open_at(exit_gate);
+    }
+}
@@ -1,4 +1,230 @@
 use bevy::prelude::*;
+use bevy_spritesheet_animation::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    animations::{
+        build::AniRegistry,
+        loader::{AniHandles, AniSet},
+    },
+    characters::{builder::spawn_character, entities::CharacterId},
+    interactions::damage::Death,
+};
+
+use super::{map::get_map_from_position, position::Position, scene::ChunkMap, stage::GameStage};
 
 #[derive(Component, Debug, Clone)]
 pub struct Grave;
+
+// No more rising from this one.
+#[derive(Component, Debug, Clone)]
+pub struct Sealed;
+
+// Burst of monsters at some point of the run.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Wave {
+    pub at: f32,
+    pub count: usize,
+}
+
+// Spawner config for the n-th 💀 of the map, in reading order.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct GraveDef {
+    // `character_id` of the enemy to copy
+    pub template: CharacterId,
+    pub respawn_delay: f32,
+    pub max_alive: usize,
+    #[serde(default)]
+    pub waves: Vec<Wave>,
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct Spawner {
+    pub grave_def: GraveDef,
+    pub cooldown: f32,
+    pub elapsed: f32,
+    pub next_wave: usize,
+    pub spawned_count: usize,
+}
+
+impl Spawner {
+    pub fn new(grave_def: &GraveDef) -> Self {
+        Spawner {
+            grave_def: grave_def.clone(),
+            cooldown: grave_def.respawn_delay,
+            elapsed: 0.,
+            next_wave: 0,
+            spawned_count: 0,
+        }
+    }
+
+    // How many rise after `delta` seconds, with `alive` still up from this grave.
+    pub fn tick(&mut self, delta: f32, alive: usize) -> usize {
+        self.elapsed += delta;
+        self.cooldown -= delta;
+
+        // Waves don't care about max alive
+        let mut count = 0;
+        while let Some(wave) = self.grave_def.waves.get(self.next_wave) {
+            if wave.at > self.elapsed {
+                break;
+            }
+            count += wave.count;
+            self.next_wave += 1;
+        }
+
+        if self.cooldown <= 0. {
+            self.cooldown = self.grave_def.respawn_delay;
+            if alive < self.grave_def.max_alive {
+                count += 1;
+            }
+        }
+
+        count
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SpawnedBy(pub Entity);
+
+pub fn init_grave_spawners(
+    mut commands: Commands,
+    game_stage: Res<GameStage>,
+    chunk_map: Res<ChunkMap>,
+    graves: Query<(Entity, &Position), With<Grave>>,
+) {
+    for (grave_def, at) in game_stage.0.graves.iter().zip(chunk_map.graves.iter()) {
+        let Some((entity, _)) = graves
+            .iter()
+            .find(|(_, position)| get_map_from_position(position.xy, None) == (at.x, at.y))
+        else {
+            continue;
+        };
+
+        println!("💀 Spawner:{:?} {:?}", at, grave_def.template);
+        commands.entity(entity).insert(Spawner::new(grave_def));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_grave_spawners(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut library: ResMut<AnimationLibrary>,
+    mut ani_registry: ResMut<AniRegistry>,
+    ani_handles: Res<AniHandles>,
+    ani_sets: Res<Assets<AniSet>>,
+    game_stage: Res<GameStage>,
+    mut spawners: Query<(Entity, &Position, &mut Spawner), Without<Sealed>>,
+    spawned: Query<&SpawnedBy, Without<Death>>,
+) {
+    let Some(characters) = ani_sets.get(&ani_handles.characters) else {
+        return;
+    };
+
+    for (grave_entity, grave_position, mut spawner) in spawners.iter_mut() {
+        let alive = spawned
+            .iter()
+            .filter(|spawned_by| spawned_by.0 == grave_entity)
+            .count();
+
+        let count = spawner.tick(time.delta_seconds(), alive);
+        if count == 0 {
+            continue;
+        }
+
+        let template = game_stage
+            .0
+            .enemies
+            .iter()
+            .find(|enemy| enemy.character_id == spawner.grave_def.template);
+        let Some(template) = template else {
+            println!("💀 No template:{:?}", spawner.grave_def.template);
+            continue;
+        };
+        let Some(ani) = characters
+            .0
+            .iter()
+            .find(|ani| ani.ani_type == template.ani_type)
+        else {
+            continue;
+        };
+
+        for _ in 0..count {
+            spawner.spawned_count += 1;
+
+            let mut character = template.clone();
+            character.character_id = CharacterId(format!(
+                "{}_{}",
+                template.character_id.0, spawner.spawned_count
+            ));
+
+            println!("💀 Rise:{:?}", character.character_id);
+            let entity = spawn_character(
+                &mut commands,
+                &asset_server,
+                &mut atlas_layouts,
+                &mut library,
+                &mut ani_registry,
+                ani,
+                &character,
+                grave_position.xy,
+            );
+            commands.entity(entity).insert(SpawnedBy(grave_entity));
+        }
+    }
+}
+
+// Sealed graves go dark.
+pub fn update_sealed_grave(mut graves: Query<&mut Sprite, (With<Grave>, Added<Sealed>)>) {
+    for mut sprite in graves.iter_mut() {
+        sprite.color = Color::srgb(0.4, 0.4, 0.5);
+        println!("💀 Sealed!");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_spawner(max_alive: usize, waves: Vec<Wave>) -> Spawner {
+        Spawner::new(&GraveDef {
+            template: CharacterId("skeleton".to_string()),
+            respawn_delay: 2.,
+            max_alive,
+            waves,
+        })
+    }
+
+    #[test]
+    fn test_respawn_delay() {
+        let mut spawner = get_spawner(3, vec![]);
+        assert_eq!(spawner.tick(1., 0), 0);
+        assert_eq!(spawner.tick(1., 0), 1);
+        assert_eq!(spawner.tick(1., 1), 0);
+        assert_eq!(spawner.tick(1., 1), 1);
+    }
+
+    #[test]
+    fn test_max_alive() {
+        let mut spawner = get_spawner(2, vec![]);
+        assert_eq!(spawner.tick(2., 2), 0);
+        // The delay starts over even when full
+        assert_eq!(spawner.tick(1., 1), 0);
+        assert_eq!(spawner.tick(1., 1), 1);
+    }
+
+    #[test]
+    fn test_waves() {
+        let waves = vec![Wave { at: 1., count: 3 }, Wave { at: 1.5, count: 2 }];
+        let mut spawner = get_spawner(1, waves);
+        assert_eq!(spawner.tick(0.5, 0), 0);
+        assert_eq!(spawner.tick(0.5, 0), 3);
+        // Waves ignore max alive, the respawn on the same tick doesn't
+        assert_eq!(spawner.tick(1., 3), 2);
+        assert_eq!(spawner.next_wave, 2);
+        assert_eq!(spawner.tick(10., 0), 1);
+    }
+}
@@ -0,0 +1,156 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    characters::entities::CharacterKind,
+    entry::game::OnGameScreen,
+    interactions::toggle::{Interactable, InteractionTarget, Toggle, ToggleEvent},
+};
+
+use super::{
+    gate::{GateId, Gates},
+    layer::{SpriteLayer, YSort},
+    map::{convert_map_to_screen, get_map_from_position, get_position_from_map, MapPosition},
+    position::Position,
+    scene::{ChunkMap, Decor},
+    stage::GameStage,
+};
+
+#[derive(Component, Debug, Clone)]
+pub struct Lever;
+
+#[derive(Component, Debug, Clone)]
+pub struct PressurePlate;
+
+#[derive(Component, Debug, Clone)]
+pub struct Switched;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchKind {
+    Lever,
+    Plate,
+}
+
+// Lever or plate from the stage, `gate` is `entrance`, `exit` or a gate id e.g. `gate_1`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct SwitchDef {
+    pub kind: SwitchKind,
+    pub cell: String,
+    pub gate: String,
+}
+
+fn find_gate_entity(
+    gate: &str,
+    chunk_map: &ChunkMap,
+    gates: &Gates,
+    gate_entities: &Query<(Entity, &GateId)>,
+) -> Option<Entity> {
+    let at = |at: &Option<MapPosition>| {
+        at.as_ref().and_then(|at| {
+            gates
+                .0
+                .iter()
+                .find(|(_, it)| it.at == *at)
+                .map(|(id, _)| id.clone())
+        })
+    };
+    let gate_id = match gate {
+        "entrance" => at(&chunk_map.entrance_gate)?,
+        "exit" => at(&chunk_map.exit_gate)?,
+        id => id.to_string(),
+    };
+
+    gate_entities
+        .iter()
+        .find(|(_, id)| id.0 == gate_id)
+        .map(|(entity, _)| entity)
+}
+
+pub fn init_switches(
+    mut commands: Commands,
+    game_stage: Res<GameStage>,
+    chunk_map: Res<ChunkMap>,
+    gates: Res<Gates>,
+    gate_entities: Query<(Entity, &GateId)>,
+) {
+    for switch_def in game_stage.0.switches.iter() {
+        let Some((x, y)) = convert_map_to_screen(switch_def.cell.clone()) else {
+            println!("🎚 Bad cell:{}", switch_def.cell);
+            continue;
+        };
+        let Some(gate_entity) =
+            find_gate_entity(&switch_def.gate, &chunk_map, &gates, &gate_entities)
+        else {
+            println!("🎚 No gate:{}", switch_def.gate);
+            continue;
+        };
+
+        let (interactable, size) = match switch_def.kind {
+            SwitchKind::Lever => (Interactable::Lever, Vec2::new(6., 16.)),
+            SwitchKind::Plate => (Interactable::PressurePlate, Vec2::new(24., 6.)),
+        };
+        let transform = get_position_from_map(x, y, None);
+        let mut entity_commands = commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::srgb(0.6, 0.45, 0.25),
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform,
+                ..default()
+            },
+            SpriteLayer::Ground,
+            Decor,
+            YSort(0.0),
+            OnGameScreen,
+            interactable,
+            InteractionTarget(gate_entity),
+            Position {
+                xy: transform.translation.xy(),
+            },
+        ));
+        match switch_def.kind {
+            SwitchKind::Lever => entity_commands.insert(Lever),
+            SwitchKind::Plate => entity_commands.insert(PressurePlate),
+        };
+
+        println!(
+            "🎚 {:?} at:{} → {}",
+            switch_def.kind, switch_def.cell, switch_def.gate
+        );
+    }
+}
+
+pub fn update_lever(mut lever_query: Query<&mut Sprite, (With<Lever>, Added<Switched>)>) {
+    for mut sprite in lever_query.iter_mut() {
+        sprite.flip_x = !sprite.flip_x;
+        println!("🎚 Switched!");
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_pressure_plate(
+    mut commands: Commands,
+    plates: Query<(Entity, &Position), (With<PressurePlate>, Without<Switched>)>,
+    characters: Query<(&Position, &CharacterKind)>,
+    mut toggle_events: EventWriter<ToggleEvent>,
+) {
+    for (entity, plate_position) in plates.iter() {
+        let plate_at = get_map_from_position(plate_position.xy, None);
+
+        if let Some((position, kind)) = characters
+            .iter()
+            .find(|(position, _)| get_map_from_position(position.xy, None) == plate_at)
+        {
+            // Once, not every tick until the toggle land
+            commands.entity(entity).insert(Switched);
+            toggle_events.send(ToggleEvent(Toggle {
+                position: position.xy,
+                by: *kind,
+                target: entity,
+            }));
+        }
+    }
+}
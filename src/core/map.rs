@@ -19,10 +19,20 @@ pub struct PathCost {
 }
 
 fn successors(walkables: &[Vec<bool>], &(x, y): &(usize, usize)) -> Vec<((usize, usize), usize)> {
-    vec![(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
-        .into_iter()
-        .filter_map(|(nx, ny)| walkables[ny][nx].then_some(((nx, ny), 1)))
-        .collect()
+    // Gates sit on the edges, so guard against stepping out of the map.
+    vec![
+        (x.checked_sub(1), Some(y)),
+        (x.checked_add(1), Some(y)),
+        (Some(x), y.checked_sub(1)),
+        (Some(x), y.checked_add(1)),
+    ]
+    .into_iter()
+    .filter_map(|(nx, ny)| {
+        let (nx, ny) = (nx?, ny?);
+        let is_walkable = *walkables.get(ny)?.get(nx)?;
+        is_walkable.then_some(((nx, ny), 1))
+    })
+    .collect()
 }
 
 fn distance(&(x1, y1): &(usize, usize), &(x2, y2): &(usize, usize)) -> usize {
@@ -109,7 +119,7 @@ pub fn find_path(
     }
 }
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct MapPosition {
     pub x: usize,
     pub y: usize,
@@ -122,6 +132,33 @@ impl MapPosition {
     }
 }
 
+pub fn find_adjacent(
+    map: &[Vec<String>],
+    at: &MapPosition,
+    target_char: &str,
+) -> Option<MapPosition> {
+    [(0, -1), (0, 1), (-1, 0), (1, 0)]
+        .into_iter()
+        .filter_map(|(dx, dy)| {
+            let x = at.x.checked_add_signed(dx)?;
+            let y = at.y.checked_add_signed(dy)?;
+            (map.get(y)?.get(x)? == target_char).then_some(MapPosition { x, y })
+        })
+        .next()
+}
+
+// Walkable cells next to `at`, e.g. to stand by a gate.
+pub fn find_adjacent_walkables(walkables: &[Vec<bool>], at: &MapPosition) -> Vec<MapPosition> {
+    [(0, -1), (0, 1), (-1, 0), (1, 0)]
+        .into_iter()
+        .filter_map(|(dx, dy)| {
+            let x = at.x.checked_add_signed(dx)?;
+            let y = at.y.checked_add_signed(dy)?;
+            (*walkables.get(y)?.get(x)?).then_some(MapPosition { x, y })
+        })
+        .collect()
+}
+
 pub fn load_map_from_csv(
     file_path: &str,
 ) -> Result<(Vec<Vec<bool>>, MapPosition, MapPosition, GameMap)> {
@@ -192,6 +229,11 @@ pub fn convert_map_to_screen(map_coord: String) -> Option<(usize, usize)> {
     Some((x, y))
 }
 
+// Back to the cell name, e.g. (5, 1) → f2.
+pub fn convert_screen_to_map(x: usize, y: usize) -> String {
+    format!("{}{}", (b'a' + x as u8) as char, y + 1)
+}
+
 // TODO decouple bevy Resource
 #[derive(Resource, Clone)]
 pub struct MapConfig {
@@ -222,7 +264,6 @@ pub fn get_position_from_map(x: usize, y: usize, map_config: Option<MapConfig>)
     )
 }
 
-#[allow(dead_code)]
 pub fn get_map_from_position(xy: Vec2, map_config: Option<MapConfig>) -> (usize, usize) {
     // Use the provided map_config or create a default instance
     let map_config = map_config.unwrap_or_default();
@@ -237,6 +278,21 @@ pub fn get_map_from_position(xy: Vec2, map_config: Option<MapConfig>) -> (usize,
     (x, y)
 }
 
+// Like `get_map_from_position` but `None` off the map, e.g. a click left of the first cell.
+pub fn get_cell_from_position(xy: Vec2, walkables: &[Vec<bool>]) -> Option<(usize, usize)> {
+    let map_config = MapConfig::default();
+    let x = (xy.x + map_config.half_width - map_config.offset.0) / map_config.cell_size as f32;
+    let y =
+        (-1.0 * xy.y + map_config.half_height - map_config.offset.1) / map_config.cell_size as f32;
+    if x.round() < 0. || y.round() < 0. {
+        return None;
+    }
+
+    let (x, y) = (x.round() as usize, y.round() as usize);
+    walkables.get(y)?.get(x)?;
+    Some((x, y))
+}
+
 #[cfg(test)]
 mod tests {
     use bevy::math::Vec3Swizzles;
@@ -293,6 +349,37 @@ mod tests {
         assert_eq!(path_cost.path, expected_path);
     }
 
+    #[test]
+    fn test_find_path_through_edge_gate() {
+        // Open gate on the top edge
+        let walkables = vec![
+            vec![false, true, false],
+            vec![false, true, false],
+            vec![false, true, false],
+        ];
+
+        let result = find_path(&walkables, (1, 0), (1, 2), false);
+        assert_eq!(result.unwrap().path, vec![(1, 0), (1, 1), (1, 2)]);
+
+        let result = find_path(&walkables, (1, 2), (1, 0), false);
+        assert_eq!(result.unwrap().path, vec![(1, 2), (1, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn test_find_adjacent() {
+        let map = vec![
+            vec!["🌳".to_string(), "🚪".to_string(), "🌳".to_string()],
+            vec!["🌳".to_string(), "🆒".to_string(), "🌳".to_string()],
+        ];
+
+        let at = MapPosition { x: 1, y: 1 };
+        assert_eq!(
+            find_adjacent(&map, &at, "🚪"),
+            Some(MapPosition { x: 1, y: 0 })
+        );
+        assert_eq!(find_adjacent(&map, &at, "💰"), None);
+    }
+
     #[test]
     fn test_convert_map_to_screen() {
         // Test cases for the function
@@ -344,4 +431,51 @@ mod tests {
         // Assert that the original map coordinates are recovered
         assert_eq!((x, y), (map_x, map_y));
     }
+
+    #[test]
+    fn test_convert_screen_to_map() {
+        assert_eq!(convert_screen_to_map(5, 1), "f2");
+        assert_eq!(
+            convert_map_to_screen(convert_screen_to_map(2, 4)),
+            Some((2, 4))
+        );
+    }
+
+    #[test]
+    fn test_get_cell_off_the_map() {
+        let walkables = vec![vec![true; 8]; 8];
+        let at = |x, y| get_position_from_map(x, y, None).translation.xy();
+
+        assert_eq!(get_cell_from_position(at(2, 3), &walkables), Some((2, 3)));
+        assert_eq!(
+            get_cell_from_position(at(0, 0) - Vec2::X * 40., &walkables),
+            None
+        );
+        assert_eq!(
+            get_cell_from_position(at(0, 0) + Vec2::Y * 40., &walkables),
+            None
+        );
+        assert_eq!(
+            get_cell_from_position(at(7, 7) + Vec2::X * 40., &walkables),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_adjacent_walkables() {
+        let walkables = vec![
+            vec![true, false, true],
+            vec![false, false, true],
+            vec![true, true, true],
+        ];
+
+        assert_eq!(
+            find_adjacent_walkables(&walkables, &MapPosition { x: 1, y: 1 }),
+            vec![MapPosition { x: 1, y: 2 }, MapPosition { x: 2, y: 1 }]
+        );
+        assert!(
+            find_adjacent_walkables(&walkables, &MapPosition { x: 0, y: 1 })
+                .contains(&MapPosition { x: 0, y: 0 })
+        );
+    }
 }
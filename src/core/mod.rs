@@ -1,12 +1,18 @@
 pub mod chest;
+pub mod faction;
 pub mod gate;
 pub mod grave;
 pub mod layer;
+pub mod lever;
 pub mod map;
 pub mod menu;
+pub mod objective;
 pub mod point;
 pub mod position;
+pub mod save;
 pub mod scene;
+pub mod seed;
 pub mod setup;
 pub mod stage;
 pub mod state;
+pub mod tick;
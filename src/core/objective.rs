@@ -0,0 +1,379 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    characters::entities::{CharacterId, CharacterKind},
+    dialogs::ask::{AskDialogContent, AskDialogEvent},
+    entry::Language,
+    interactions::death::DeathEvent,
+};
+
+use super::{
+    chest::{ChestState, Chests},
+    map::get_map_from_position,
+    point::Exit,
+    position::Position,
+    stage::GameStage,
+    state::GameState,
+};
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    ReachExit,
+    OpenChests(usize),
+    KillAllMonsters,
+    Survive(f32),
+    Protect(CharacterId),
+}
+
+pub fn default_objectives() -> Vec<Objective> {
+    vec![Objective::ReachExit]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectiveStatus {
+    Pending,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjectiveProgress {
+    pub objective: Objective,
+    pub current: f32,
+    pub target: f32,
+    pub status: ObjectiveStatus,
+}
+
+impl ObjectiveProgress {
+    pub fn describe(&self, language: &Language) -> String {
+        let check = match self.status {
+            ObjectiveStatus::Pending => "[ ]",
+            ObjectiveStatus::Done => "[x]",
+            ObjectiveStatus::Failed => "[!]",
+        };
+        let text = match &self.objective {
+            Objective::ReachExit => language.translate("Reach exit").to_owned(),
+            Objective::OpenChests(_) => format!(
+                "{} {}/{}",
+                language.translate("Open chests"),
+                self.current,
+                self.target
+            ),
+            Objective::KillAllMonsters => format!(
+                "{} {}/{}",
+                language.translate("Kill monsters"),
+                self.current,
+                self.target
+            ),
+            Objective::Survive(_) => format!(
+                "{} {:.0}/{:.0}s",
+                language.translate("Survive"),
+                self.current,
+                self.target
+            ),
+            Objective::Protect(character_id) => {
+                format!("{} {}", language.translate("Protect"), character_id.0)
+            }
+        };
+
+        format!("{check} {text}")
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct ObjectiveTracker {
+    pub objectives: Vec<ObjectiveProgress>,
+    pub elapsed: f32,
+    pub is_exit_reached: bool,
+    pub is_party_dead: bool,
+    pub fallen: Vec<(CharacterId, CharacterKind)>,
+}
+
+impl ObjectiveTracker {
+    pub fn new(objectives: &[Objective]) -> Self {
+        Self {
+            objectives: objectives
+                .iter()
+                .map(|objective| ObjectiveProgress {
+                    objective: objective.clone(),
+                    current: 0.,
+                    target: 0.,
+                    status: ObjectiveStatus::Pending,
+                })
+                .collect(),
+            ..default()
+        }
+    }
+
+    // All done except reaching the exit, time to open the exit gate.
+    // Protect is only done at the exit, it just mustn't have failed.
+    pub fn is_ready_to_exit(&self) -> bool {
+        self.objectives
+            .iter()
+            .filter(|progress| progress.objective != Objective::ReachExit)
+            .all(|progress| match progress.objective {
+                Objective::Protect(_) => progress.status != ObjectiveStatus::Failed,
+                _ => progress.status == ObjectiveStatus::Done,
+            })
+    }
+
+    // Progress from the counts of this tick, `elapsed`, `is_exit_reached` and `fallen` are kept here.
+    pub fn refresh(
+        &mut self,
+        opened_chests: usize,
+        total_chests: usize,
+        killed_monsters: usize,
+        total_monsters: usize,
+    ) {
+        let elapsed = self.elapsed;
+        let is_exit_reached = self.is_exit_reached;
+        let fallen = self.fallen.clone();
+
+        for progress in self.objectives.iter_mut() {
+            let (current, target, is_failed) = match &progress.objective {
+                Objective::ReachExit => (if is_exit_reached { 1. } else { 0. }, 1., false),
+                Objective::OpenChests(count) => (
+                    opened_chests as f32,
+                    (*count).min(total_chests) as f32,
+                    false,
+                ),
+                Objective::KillAllMonsters => {
+                    (killed_monsters as f32, total_monsters as f32, false)
+                }
+                Objective::Survive(seconds) => (elapsed.min(*seconds), *seconds, false),
+                // Kept alive up to the exit
+                Objective::Protect(protect_id) => {
+                    let is_dead = fallen
+                        .iter()
+                        .any(|(character_id, _)| character_id == protect_id);
+                    (if is_exit_reached { 1. } else { 0. }, 1., is_dead)
+                }
+            };
+
+            progress.current = current;
+            progress.target = target;
+
+            // Failed stay failed
+            if progress.status != ObjectiveStatus::Failed {
+                progress.status = match (is_failed, current >= target) {
+                    (true, _) => ObjectiveStatus::Failed,
+                    (false, true) => ObjectiveStatus::Done,
+                    (false, false) => ObjectiveStatus::Pending,
+                };
+            }
+        }
+    }
+
+    pub fn is_clear(&self) -> bool {
+        !self.objectives.is_empty()
+            && self
+                .objectives
+                .iter()
+                .all(|progress| progress.status == ObjectiveStatus::Done)
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.is_party_dead
+            || self
+                .objectives
+                .iter()
+                .any(|progress| progress.status == ObjectiveStatus::Failed)
+    }
+}
+
+pub fn init_objectives(mut commands: Commands, game_stage: Res<GameStage>) {
+    commands.insert_resource(ObjectiveTracker::new(&game_stage.0.objectives));
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_objectives(
+    time: Res<Time>,
+    mut tracker: ResMut<ObjectiveTracker>,
+    chests: Res<Chests>,
+    characters: Query<(&CharacterKind, &Position, &CharacterId)>,
+    exits: Query<&Position, With<Exit>>,
+    mut death_events: EventReader<DeathEvent>,
+    mut ask_dialog_events: EventWriter<AskDialogEvent>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    tracker.elapsed += time.delta_seconds();
+
+    // Corpses get despawned, remember who fell.
+    for death in death_events.read() {
+        tracker
+            .fallen
+            .push((death.character_id.clone(), death.kind));
+    }
+    let fallen_count = |of_kind: CharacterKind| {
+        tracker
+            .fallen
+            .iter()
+            .filter(|(_, kind)| *kind == of_kind)
+            .count()
+    };
+    let alive_count = |of_kind: CharacterKind| {
+        characters
+            .iter()
+            .filter(|(kind, _, _)| **kind == of_kind)
+            .count()
+    };
+
+    // A hero on the exit cell, under thinker or direct control
+    let exit_cells = exits
+        .iter()
+        .map(|position| get_map_from_position(position.xy, None))
+        .collect::<Vec<_>>();
+    let at_exit = characters.iter().find(|(kind, position, _)| {
+        **kind == CharacterKind::Human
+            && exit_cells.contains(&get_map_from_position(position.xy, None))
+    });
+    if let Some((_, position, character_id)) = at_exit {
+        if !tracker.is_exit_reached {
+            ask_dialog_events.send(AskDialogEvent(AskDialogContent {
+                position: position.xy,
+                by: character_id.clone(),
+                content: "I did it!".to_owned(),
+            }));
+        }
+        tracker.is_exit_reached = true;
+    }
+
+    let opened_chests = chests
+        .0
+        .values()
+        .filter(|chest| chest.status == ChestState::Open)
+        .count();
+    let killed_monsters = fallen_count(CharacterKind::Monster);
+    let total_monsters = killed_monsters + alive_count(CharacterKind::Monster);
+
+    // Lose only when the whole party is down
+    let is_party_dead =
+        fallen_count(CharacterKind::Human) > 0 && alive_count(CharacterKind::Human) == 0;
+
+    tracker.refresh(
+        opened_chests,
+        chests.0.len(),
+        killed_monsters,
+        total_monsters,
+    );
+    tracker.is_party_dead = is_party_dead;
+
+    if tracker.is_over() {
+        println!("💥  GameState::Over");
+        game_state.set(GameState::Over);
+    } else if tracker.is_clear() {
+        println!("🎉  GameState::Clear");
+        game_state.set(GameState::Clear);
+    }
+}
+
+#[derive(Component)]
+pub struct ObjectiveHud;
+
+pub fn update_objective_hud(
+    tracker: Res<ObjectiveTracker>,
+    language: Res<Language>,
+    mut query: Query<&mut Text, With<ObjectiveHud>>,
+) {
+    if !tracker.is_changed() {
+        return;
+    }
+
+    // The tracker change every tick with `elapsed`, the text much less
+    let value = tracker
+        .objectives
+        .iter()
+        .map(|progress| progress.describe(&language))
+        .collect::<Vec<_>>()
+        .join("\n");
+    for mut text in query.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_status(tracker: &ObjectiveTracker) -> Vec<ObjectiveStatus> {
+        tracker
+            .objectives
+            .iter()
+            .map(|progress| progress.status)
+            .collect()
+    }
+
+    #[test]
+    fn test_protect_until_exit() {
+        let vip = CharacterId("man_1".to_string());
+        let mut tracker = ObjectiveTracker::new(&[Objective::Protect(vip), Objective::ReachExit]);
+
+        tracker.refresh(0, 0, 0, 0);
+        assert_eq!(get_status(&tracker)[0], ObjectiveStatus::Pending);
+        assert!(tracker.is_ready_to_exit());
+        assert!(!tracker.is_clear());
+
+        tracker.is_exit_reached = true;
+        tracker.refresh(0, 0, 0, 0);
+        assert!(tracker.is_clear());
+    }
+
+    #[test]
+    fn test_protect_failed() {
+        let vip = CharacterId("man_1".to_string());
+        let mut tracker = ObjectiveTracker::new(&[Objective::Protect(vip.clone())]);
+
+        tracker.fallen.push((vip, CharacterKind::Human));
+        tracker.refresh(0, 0, 0, 0);
+        assert!(tracker.is_over());
+        assert!(!tracker.is_ready_to_exit());
+
+        // Failed stay failed
+        tracker.fallen.clear();
+        tracker.refresh(0, 0, 0, 0);
+        assert_eq!(get_status(&tracker), vec![ObjectiveStatus::Failed]);
+    }
+
+    #[test]
+    fn test_counts() {
+        let mut tracker = ObjectiveTracker::new(&[
+            Objective::OpenChests(3),
+            Objective::KillAllMonsters,
+            Objective::Survive(10.),
+            Objective::ReachExit,
+        ]);
+
+        tracker.elapsed = 5.;
+        tracker.refresh(1, 2, 1, 2);
+        assert!(get_status(&tracker)
+            .iter()
+            .all(|status| *status == ObjectiveStatus::Pending));
+
+        // Can't ask for more chests than the map has
+        tracker.elapsed = 12.;
+        tracker.refresh(2, 2, 2, 2);
+        assert_eq!(
+            get_status(&tracker),
+            vec![
+                ObjectiveStatus::Done,
+                ObjectiveStatus::Done,
+                ObjectiveStatus::Done,
+                ObjectiveStatus::Pending,
+            ]
+        );
+        assert_eq!(
+            tracker.objectives[2].describe(&Language::English),
+            "[x] Survive 10/10s"
+        );
+        assert_eq!(
+            tracker.objectives[2].describe(&Language::Spanish),
+            "[x] Sobrevivir 10/10s"
+        );
+        assert!(tracker.is_ready_to_exit());
+        assert!(!tracker.is_clear());
+    }
+}
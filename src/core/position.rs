@@ -1,7 +1,10 @@
-use crate::Component;
-use crate::Vec2;
+use bevy::prelude::{Component, Vec2};
 
 #[derive(Component, Debug, Copy, Clone, Default)]
 pub struct Position {
     pub xy: Vec2,
 }
+
+// Where it was at the previous tick, to draw in between.
+#[derive(Component, Debug, Copy, Clone, Default)]
+pub struct PreviousPosition(pub Vec2);
@@ -0,0 +1,469 @@
+use bevy::prelude::*;
+use bevy_pkv::PkvStore;
+use bevy_spritesheet_animation::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animations::{
+        build::AniRegistry,
+        loader::{AniHandles, AniSet},
+    },
+    characters::{
+        bar::{Health, Morale, Stamina},
+        builder::spawn_character,
+        effect::{StatusEffect, StatusEffects},
+        entities::{CharacterId, CharacterKind},
+    },
+    interactions::death::Inventory,
+};
+
+use super::{
+    chest::{ChestState, Chests},
+    gate::{GateState, Gates},
+    grave::{Sealed, SpawnedBy, Spawner},
+    map::{get_map_from_position, MapPosition},
+    objective::ObjectiveTracker,
+    position::{Position, PreviousPosition},
+    scene::ChunkMap,
+    seed::{RunSeed, StageSeed},
+    stage::GameStage,
+    state::LoadError,
+};
+
+// Bump when `SaveGame` change shape, older saves are then ignored.
+pub const SAVE_VERSION: u32 = 3;
+const SAVE_KEY: &str = "save";
+// Seconds between two autosaves.
+pub const AUTOSAVE_STEP: f32 = 5.;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedCharacter {
+    pub id: String,
+    pub x: f32,
+    pub y: f32,
+    pub health: f32,
+    pub stamina: Option<f32>,
+    pub morale: Option<f32>,
+    pub effects: Vec<StatusEffect>,
+    // Grave cell it rose from, to rise again on load
+    pub risen_from: Option<[usize; 2]>,
+}
+
+// Where a grave is in its waves and respawns.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedGrave {
+    pub cell: [usize; 2],
+    pub elapsed: f32,
+    pub cooldown: f32,
+    pub next_wave: usize,
+    pub spawned_count: usize,
+    pub is_sealed: bool,
+}
+
+// Only the version, to check before reading the rest.
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SaveGame {
+    pub version: u32,
+    pub seed: u64,
+    pub public_key: String,
+    pub stage_id: String,
+    // Entrance and exit, the map come back from the seed.
+    pub chunk: [[usize; 2]; 2],
+    pub elapsed: f32,
+    pub characters: Vec<SavedCharacter>,
+    pub fallen: Vec<(String, CharacterKind)>,
+    pub opened_chests: Vec<String>,
+    pub opened_gates: Vec<String>,
+    pub inventory: Vec<String>,
+    pub graves: Vec<SavedGrave>,
+}
+
+impl SaveGame {
+    pub fn from_json(json: &str) -> Option<Self> {
+        let header = match serde_json::from_str::<SaveHeader>(json) {
+            Ok(header) => header,
+            Err(err) => {
+                println!("💾 Broken save: {}", err);
+                return None;
+            }
+        };
+
+        if header.version != SAVE_VERSION {
+            println!(
+                "💾 Save v{} ignored, expected v{}",
+                header.version, SAVE_VERSION
+            );
+            return None;
+        }
+
+        serde_json::from_str::<SaveGame>(json)
+            .map_err(|err| println!("💾 Broken save: {}", err))
+            .ok()
+    }
+}
+
+fn get_chunk(chunk_map: &ChunkMap) -> [[usize; 2]; 2] {
+    let cell = |at: &MapPosition| [at.x, at.y];
+    [cell(&chunk_map.entrance), cell(&chunk_map.exit)]
+}
+
+fn get_cell(position: &Position) -> [usize; 2] {
+    let (x, y) = get_map_from_position(position.xy, None);
+    [x, y]
+}
+
+// Same seed but another map or stage, e.g. the generator changed.
+fn is_matching(save_game: &SaveGame, game_stage: &GameStage, chunk_map: &ChunkMap) -> bool {
+    save_game.stage_id == game_stage.0.id && save_game.chunk == get_chunk(chunk_map)
+}
+
+// Save waiting to be applied once the stage is up, set by "Continue".
+#[derive(Resource, Default, Debug)]
+pub struct PendingSave(pub Option<SaveGame>);
+
+pub fn load_game(pkv: &PkvStore) -> Option<SaveGame> {
+    pkv.get::<String>(SAVE_KEY)
+        .ok()
+        .and_then(|json| SaveGame::from_json(&json))
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn save_game(
+    mut pkv: ResMut<PkvStore>,
+    run_seed: Res<RunSeed>,
+    stage_seed: Res<StageSeed>,
+    game_stage: Res<GameStage>,
+    chunk_map: Res<ChunkMap>,
+    tracker: Res<ObjectiveTracker>,
+    chests: Res<Chests>,
+    gates: Res<Gates>,
+    inventory: Res<Inventory>,
+    load_error: Res<LoadError>,
+    characters: Query<(
+        &CharacterId,
+        &Position,
+        &Health,
+        Option<&Stamina>,
+        Option<&Morale>,
+        Option<&StatusEffects>,
+        Option<&SpawnedBy>,
+    )>,
+    graves: Query<(&Position, &Spawner, Option<&Sealed>)>,
+) {
+    // Nothing worth keeping from a stage that didn't load
+    if load_error.0.is_some() {
+        return;
+    }
+
+    let opened = |open: bool, id: &String| open.then(|| id.clone());
+    let save_game = SaveGame {
+        version: SAVE_VERSION,
+        seed: run_seed.0,
+        public_key: stage_seed.0.clone(),
+        stage_id: game_stage.0.id.clone(),
+        chunk: get_chunk(&chunk_map),
+        elapsed: tracker.elapsed,
+        characters: characters
+            .iter()
+            .map(
+                |(character_id, position, health, stamina, morale, effects, spawned_by)| {
+                    SavedCharacter {
+                        id: character_id.0.clone(),
+                        x: position.xy.x,
+                        y: position.xy.y,
+                        health: health.value,
+                        stamina: stamina.map(|stamina| stamina.value),
+                        morale: morale.map(|morale| morale.value),
+                        effects: effects.map(|effects| effects.0.clone()).unwrap_or_default(),
+                        risen_from: spawned_by
+                            .and_then(|spawned_by| graves.get(spawned_by.0).ok())
+                            .map(|(position, ..)| get_cell(position)),
+                    }
+                },
+            )
+            .collect(),
+        fallen: tracker
+            .fallen
+            .iter()
+            .map(|(character_id, kind)| (character_id.0.clone(), *kind))
+            .collect(),
+        opened_chests: chests
+            .0
+            .iter()
+            .filter_map(|(id, chest)| opened(chest.status == ChestState::Open, id))
+            .collect(),
+        opened_gates: gates
+            .0
+            .iter()
+            .filter_map(|(id, gate)| opened(gate.status == GateState::Open, id))
+            .collect(),
+        inventory: inventory.0.clone(),
+        graves: graves
+            .iter()
+            .map(|(position, spawner, sealed)| SavedGrave {
+                cell: get_cell(position),
+                elapsed: spawner.elapsed,
+                cooldown: spawner.cooldown,
+                next_wave: spawner.next_wave,
+                spawned_count: spawner.spawned_count,
+                is_sealed: sealed.is_some(),
+            })
+            .collect(),
+    };
+
+    match serde_json::to_string(&save_game) {
+        Ok(json) => {
+            if let Err(err) = pkv.set_string(SAVE_KEY, &json) {
+                println!("💾 Can't save: {}", err);
+            }
+        }
+        Err(err) => println!("💾 Can't serialize: {}", err),
+    }
+}
+
+// The run is over, nothing to continue.
+pub fn delete_save(mut pkv: ResMut<PkvStore>) {
+    if pkv.get::<String>(SAVE_KEY).is_ok() {
+        println!("💾 Save deleted");
+        let _ = pkv.remove(SAVE_KEY);
+    }
+}
+
+// Raise again the ones risen from graves, before `apply_save` put everyone back.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_saved_risen(
+    mut commands: Commands,
+    pending_save: Res<PendingSave>,
+    game_stage: Res<GameStage>,
+    chunk_map: Res<ChunkMap>,
+    asset_server: Res<AssetServer>,
+    mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut library: ResMut<AnimationLibrary>,
+    mut ani_registry: ResMut<AniRegistry>,
+    ani_handles: Res<AniHandles>,
+    ani_sets: Res<Assets<AniSet>>,
+    graves: Query<(Entity, &Position, &Spawner)>,
+) {
+    let Some(save_game) = &pending_save.0 else {
+        return;
+    };
+    let Some(ani_set) = ani_sets.get(&ani_handles.characters) else {
+        return;
+    };
+    if !is_matching(save_game, &game_stage, &chunk_map) {
+        return;
+    }
+
+    for character in save_game.characters.iter() {
+        let Some(cell) = character.risen_from else {
+            continue;
+        };
+        let Some((grave_entity, _, spawner)) = graves
+            .iter()
+            .find(|(_, position, _)| get_cell(position) == cell)
+        else {
+            continue;
+        };
+        let Some(template) = game_stage
+            .0
+            .enemies
+            .iter()
+            .find(|enemy| enemy.character_id == spawner.grave_def.template)
+        else {
+            continue;
+        };
+        let Some(ani) = ani_set
+            .0
+            .iter()
+            .find(|ani| ani.ani_type == template.ani_type)
+        else {
+            continue;
+        };
+
+        let mut risen = template.clone();
+        risen.character_id = CharacterId(character.id.clone());
+        let entity = spawn_character(
+            &mut commands,
+            &asset_server,
+            &mut atlas_layouts,
+            &mut library,
+            &mut ani_registry,
+            ani,
+            &risen,
+            Vec2::new(character.x, character.y),
+        );
+        commands.entity(entity).insert(SpawnedBy(grave_entity));
+    }
+}
+
+// Put everyone back where they were, after `init_characters` and `spawn_saved_risen`.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn apply_save(
+    mut commands: Commands,
+    mut pending_save: ResMut<PendingSave>,
+    game_stage: Res<GameStage>,
+    chunk_map: Res<ChunkMap>,
+    mut tracker: ResMut<ObjectiveTracker>,
+    mut chests: ResMut<Chests>,
+    mut gates: ResMut<Gates>,
+    mut inventory: ResMut<Inventory>,
+    mut characters: Query<(
+        Entity,
+        &CharacterId,
+        &mut Position,
+        &mut PreviousPosition,
+        &mut Transform,
+        &mut Health,
+        Option<&mut Stamina>,
+        Option<&mut Morale>,
+        Option<&mut StatusEffects>,
+    )>,
+    mut graves: Query<(Entity, &Position, &mut Spawner), Without<CharacterId>>,
+) {
+    let Some(save_game) = pending_save.0.take() else {
+        return;
+    };
+
+    if !is_matching(&save_game, &game_stage, &chunk_map) {
+        println!("💾 Save doesn't match stage:{}", game_stage.0.id);
+        return;
+    }
+
+    println!("💾 Continue seed:{}", save_game.seed);
+    for (
+        entity,
+        character_id,
+        mut position,
+        mut previous,
+        mut transform,
+        mut health,
+        stamina,
+        morale,
+        effects,
+    ) in characters.iter_mut()
+    {
+        let Some(character) = save_game
+            .characters
+            .iter()
+            .find(|character| character.id == character_id.0)
+        else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        position.xy = Vec2::new(character.x, character.y);
+        previous.0 = position.xy;
+        transform.translation.x = character.x;
+        transform.translation.y = character.y;
+        health.value = character.health.clamp(0., health.max);
+        if let (Some(mut stamina), Some(value)) = (stamina, character.stamina) {
+            stamina.value = value.clamp(0., stamina.max);
+        }
+        if let (Some(mut morale), Some(value)) = (morale, character.morale) {
+            morale.value = value.clamp(0., morale.max);
+        }
+        if let Some(mut effects) = effects {
+            effects.0 = character.effects.clone();
+        }
+    }
+
+    for (entity, position, mut spawner) in graves.iter_mut() {
+        let cell = get_cell(position);
+        let Some(grave) = save_game.graves.iter().find(|grave| grave.cell == cell) else {
+            continue;
+        };
+        spawner.elapsed = grave.elapsed;
+        spawner.cooldown = grave.cooldown;
+        spawner.next_wave = grave.next_wave;
+        spawner.spawned_count = grave.spawned_count;
+        if grave.is_sealed {
+            commands.entity(entity).insert(Sealed);
+        }
+    }
+
+    tracker.elapsed = save_game.elapsed;
+    tracker.fallen = save_game
+        .fallen
+        .into_iter()
+        .map(|(id, kind)| (CharacterId(id), kind))
+        .collect();
+
+    for id in save_game.opened_chests.iter() {
+        if let Some(chest) = chests.0.get_mut(id) {
+            chest.status = ChestState::Open;
+        }
+    }
+    for id in save_game.opened_gates.iter() {
+        if let Some(gate) = gates.0.get_mut(id) {
+            gate.status = GateState::Open;
+        }
+    }
+
+    inventory.0 = save_game.inventory;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::characters::effect::EffectKind;
+
+    fn get_save_game(version: u32) -> SaveGame {
+        SaveGame {
+            version,
+            seed: 42,
+            public_key: "gistmeAhMG7AcKSPCHis8JikGmKT9tRRyZpyMLNNULq".to_string(),
+            stage_id: "1-1".to_string(),
+            chunk: [[0, 1], [8, 9]],
+            elapsed: 12.5,
+            characters: vec![SavedCharacter {
+                id: "man_0".to_string(),
+                x: 16.,
+                y: -32.,
+                health: 75.,
+                stamina: Some(30.),
+                morale: None,
+                effects: vec![StatusEffect {
+                    kind: EffectKind::Poison,
+                    duration: 2.,
+                    power: 1.,
+                }],
+                risen_from: Some([3, 4]),
+            }],
+            fallen: vec![("skeleton_0".to_string(), CharacterKind::Monster)],
+            opened_chests: vec!["chest_0".to_string()],
+            opened_gates: vec![],
+            inventory: vec!["potion".to_string()],
+            graves: vec![SavedGrave {
+                cell: [3, 4],
+                elapsed: 12.5,
+                cooldown: 2.5,
+                next_wave: 1,
+                spawned_count: 4,
+                is_sealed: false,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_save_round_trip() {
+        let save_game = get_save_game(SAVE_VERSION);
+        let json = serde_json::to_string(&save_game).unwrap();
+
+        assert_eq!(SaveGame::from_json(&json), Some(save_game));
+    }
+
+    #[test]
+    fn test_old_save_is_ignored() {
+        let json = serde_json::to_string(&get_save_game(0)).unwrap();
+        assert_eq!(SaveGame::from_json(&json), None);
+
+        // Older shape, missing fields
+        assert_eq!(SaveGame::from_json(r#"{"version":0,"seed":42}"#), None);
+        assert_eq!(SaveGame::from_json("not a save"), None);
+    }
+}
@@ -2,19 +2,19 @@ use std::fs;
 
 use bevy::{color::palettes::css::RED, prelude::*};
 use bevy_spritesheet_animation::prelude::*;
-use serde_json::from_str;
 
 use crate::{
     animations::{
-        build::build_library,
+        build::{build_library, AniRegistry},
         entities::{self, Ani, AniType},
     },
     entry::game::OnGameScreen,
+    interactions::toggle::Interactable,
 };
 
 use super::{
     chest::{Chest, ChestId, ChestState, Chests},
-    gate::{Gate, GateState, Gates},
+    gate::{Gate, GateId, GateState, Gates},
     grave::Grave,
     layer::{SpriteLayer, YSort},
     map::{get_position_from_map, MapConfig, MapPosition, PathCost},
@@ -30,6 +30,8 @@ pub struct ChunkMap {
     pub walkables: Vec<Vec<bool>>,
     pub entrance: MapPosition,
     pub exit: MapPosition,
+    pub entrance_gate: Option<MapPosition>,
+    pub exit_gate: Option<MapPosition>,
     pub graves: Vec<MapPosition>,
 }
 
@@ -46,6 +48,7 @@ struct DecorBundle {
     sprite_layer: SpriteLayer,
     marker: Decor,
     ysort: YSort,
+    screen: OnGameScreen,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -54,6 +57,8 @@ pub fn build_scene(
     asset_server: &Res<AssetServer>,
     atlas_layouts: &mut ResMut<Assets<TextureAtlasLayout>>,
     library: &mut ResMut<AnimationLibrary>,
+    ani_registry: &mut ResMut<AniRegistry>,
+    decor_animations: &[Ani],
     map: GameMap,
     mut chests: ResMut<Chests>,
     mut gates: ResMut<Gates>,
@@ -93,54 +98,9 @@ pub fn build_scene(
             tile_y: true,
             stretch_value: 0.25,
         },
+        OnGameScreen,
     ));
 
-    // Load decor from JSON file
-    // let decor_json = fs::read_to_string("assets/decor.json").expect("Unable to read file");
-    let decor_json = r#"[
-  {
-    "ani_type": "chest",
-    "texture_path": "chest.png",
-    "width": 18,
-    "height": 29,
-    "animations": [
-      {
-        "action_name": "close",
-        "x": 0,
-        "y": 0,
-        "count": 1
-      },
-      {
-        "action_name": "open",
-        "x": 0,
-        "y": 1,
-        "count": 1
-      }
-    ]
-  },
-  {
-    "ani_type": "gate",
-    "texture_path": "gate.png",
-    "width": 42,
-    "height": 18,
-    "animations": [
-      {
-        "action_name": "close",
-        "x": 0,
-        "y": 0,
-        "count": 1
-      },
-      {
-        "action_name": "open",
-        "x": 0,
-        "y": 1,
-        "count": 1
-      }
-    ]
-  }
-]
-"#;
-    let decor_animations: Vec<Ani> = from_str(&decor_json).expect("Unable to parse JSON");
     let mut chest_entities = vec![];
 
     for (y, row) in map.0.iter().enumerate() {
@@ -169,6 +129,7 @@ pub fn build_scene(
                         sprite_layer: SpriteLayer::Ground,
                         marker: Decor,
                         ysort: YSort(0.0),
+                        screen: OnGameScreen,
                     });
                 }
                 "🦀" => {
@@ -187,18 +148,20 @@ pub fn build_scene(
                         sprite_layer: SpriteLayer::Ground,
                         marker: Decor,
                         ysort: YSort(0.0),
+                        screen: OnGameScreen,
                     });
                 }
                 "🚪" => {
                     let ani = decor_animations
                         .iter()
-                        .find(|ani| ani.ani_type == AniType::Gate)
+                        .find(|ani| ani.ani_type == AniType::new("gate"))
                         .expect("Expected gate");
                     let deco_bundle = build_ani_decor_bundle(
                         "gate_close".to_owned(),
                         asset_server,
                         atlas_layouts,
                         library,
+                        ani_registry,
                         ani,
                         transform
                             .with_scale(Vec3::splat(2.0))
@@ -210,26 +173,35 @@ pub fn build_scene(
                     );
 
                     let gate_id = format!("gate_{}", gates.0.len());
-                    commands.spawn(deco_bundle).insert(ChestId(gate_id.clone()));
+                    commands.spawn((
+                        deco_bundle,
+                        GateId(gate_id.clone()),
+                        Interactable::Gate,
+                        Position {
+                            xy: Vec2::new(transform.translation.x, transform.translation.y),
+                        },
+                    ));
 
                     gates.0.insert(
                         gate_id,
                         Gate {
                             status: GateState::Close,
                             key: None,
+                            at: MapPosition { x, y },
                         },
                     );
                 }
                 "💰" => {
                     let ani = decor_animations
                         .iter()
-                        .find(|ani| ani.ani_type == AniType::Chest)
+                        .find(|ani| ani.ani_type == AniType::new("chest"))
                         .expect("Expected chest");
                     let deco_bundle = build_ani_decor_bundle(
                         "chest_close".to_owned(),
                         asset_server,
                         atlas_layouts,
                         library,
+                        ani_registry,
                         ani,
                         transform
                             .with_scale(Vec3::splat(2.0))
@@ -245,6 +217,7 @@ pub fn build_scene(
                         .spawn((
                             deco_bundle,
                             ChestId(chest_id.clone()),
+                            Interactable::Chest,
                             Position {
                                 xy: Vec2::new(transform.translation.x, transform.translation.y),
                             },
@@ -277,8 +250,10 @@ pub fn build_scene(
                             sprite_layer: SpriteLayer::Ground,
                             marker: Decor,
                             ysort: YSort(0.0),
+                            screen: OnGameScreen,
                         },
                         Grave,
+                        Interactable::Grave,
                         Position {
                             xy: Vec2::new(transform.translation.x, transform.translation.y),
                         },
@@ -296,6 +271,7 @@ pub fn build_scene(
         Position {
             xy: Vec2::new(position.translation.x, position.translation.y),
         },
+        OnGameScreen,
     ));
     let position = get_position_from_map(exit.x, exit.y, None);
     commands.spawn((
@@ -303,6 +279,7 @@ pub fn build_scene(
         Position {
             xy: Vec2::new(position.translation.x, position.translation.y),
         },
+        OnGameScreen,
     ));
 
     chest_entities
@@ -313,9 +290,11 @@ pub struct AniDecorBundle {
     sprite_bundle: SpriteBundle,
     texture_atlas: TextureAtlas,
     spritesheet_animation: SpritesheetAnimation,
+    ani_type: AniType,
     sprite_layer: SpriteLayer,
     marker: Decor,
     ysort: YSort,
+    screen: OnGameScreen,
 }
 
 pub fn build_ani_decor_bundle(
@@ -323,12 +302,13 @@ pub fn build_ani_decor_bundle(
     asset_server: &Res<AssetServer>,
     atlas_layouts: &mut ResMut<Assets<TextureAtlasLayout>>,
     library: &mut ResMut<AnimationLibrary>,
+    ani_registry: &mut ResMut<AniRegistry>,
     ani: &Ani,
     transform: Transform,
 ) -> AniDecorBundle {
     let clip_fps = 30;
 
-    let libs = build_library(atlas_layouts, library, ani, clip_fps);
+    let layout = build_library(atlas_layouts, library, ani_registry, ani, clip_fps);
 
     let texture_path = ani.texture_path.clone();
     let texture = asset_server.load(texture_path);
@@ -340,14 +320,16 @@ pub fn build_ani_decor_bundle(
             ..default()
         },
         texture_atlas: TextureAtlas {
-            layout: libs[0].1.clone(),
+            layout,
             ..default()
         },
         spritesheet_animation: SpritesheetAnimation::from_id(
-            library.animation_with_name(animation_name).unwrap(),
+            ani_registry.get(animation_name).unwrap(),
         ),
+        ani_type: ani.ani_type.clone(),
         sprite_layer: SpriteLayer::Ground,
         marker: Decor,
         ysort: YSort(0.0),
+        screen: OnGameScreen,
     }
 }
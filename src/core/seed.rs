@@ -0,0 +1,85 @@
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+
+use super::state::GameState;
+
+pub const DEFAULT_PUBLIC_KEY: &str = "gistmeAhMG7AcKSPCHis8JikGmKT9tRRyZpyMLNNULq";
+
+// Seed for everything random in a run, e.g. chest and grave placement.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunSeed(pub u64);
+
+impl Default for RunSeed {
+    fn default() -> Self {
+        Self(rand::random())
+    }
+}
+
+impl RunSeed {
+    pub fn next(&self) -> Self {
+        Self(self.0.wrapping_add(1))
+    }
+}
+
+// Public key the stage map is generated from, e.g. a wallet address.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct StageSeed(pub String);
+
+impl Default for StageSeed {
+    fn default() -> Self {
+        Self(DEFAULT_PUBLIC_KEY.to_owned())
+    }
+}
+
+impl StageSeed {
+    // The first 2 chars place the gates, the rest the trees.
+    pub fn new(public_key: &str) -> Option<Self> {
+        let public_key = public_key.trim();
+        (public_key.chars().count() >= 2).then(|| Self(public_key.to_owned()))
+    }
+}
+
+// Set from outside the app, e.g. the page, until `receive_public_key` pick it up.
+static PENDING_PUBLIC_KEY: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn set_public_key(public_key: &str) {
+    if let Ok(mut pending) = PENDING_PUBLIC_KEY.lock() {
+        *pending = Some(public_key.to_owned());
+    }
+}
+
+pub fn receive_public_key(
+    mut next_stage_seed: Local<Option<StageSeed>>,
+    mut stage_seed: ResMut<StageSeed>,
+    game_state: Res<State<GameState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    let public_key = PENDING_PUBLIC_KEY
+        .lock()
+        .ok()
+        .and_then(|mut pending| pending.take());
+
+    if let Some(public_key) = public_key {
+        match StageSeed::new(&public_key) {
+            Some(new_stage_seed) if *stage_seed != new_stage_seed => {
+                println!("🌱 Stage seed:{}", new_stage_seed.0);
+                *next_stage_seed = Some(new_stage_seed);
+
+                // Regenerate the stage right away when playing
+                if *game_state.get() == GameState::Game {
+                    next_game_state.set(GameState::Loading);
+                }
+            }
+            Some(_) => (),
+            None => println!("🌱 Invalid public key:{:?}", public_key),
+        }
+    }
+
+    // Swap once out of the game, so the save on leaving keep the stage it was made on
+    if *game_state.get() != GameState::Game {
+        if let Some(new_stage_seed) = next_stage_seed.take() {
+            *stage_seed = new_stage_seed;
+        }
+    }
+}
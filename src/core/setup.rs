@@ -2,6 +2,10 @@ use bevy::prelude::*;
 use bevy_spritesheet_animation::prelude::*;
 
 use crate::{
+    animations::{
+        build::AniRegistry,
+        loader::{AniHandles, AniSet},
+    },
     core::scene::GameMap,
     entry::game::OnGameScreen,
     maps::gen::{gen_map_from_public_key, refine_walkable_map},
@@ -10,51 +14,59 @@ use crate::{
 use super::{
     chest::Chests,
     gate::Gates,
-    map::{load_map_from_csv, MapConfig},
+    map::{find_adjacent, load_map_from_csv, MapConfig},
     scene::{build_scene, ChunkMap},
+    seed::{RunSeed, StageSeed},
 };
 
+#[allow(clippy::too_many_arguments)]
 pub fn setup_scene(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
     mut library: ResMut<AnimationLibrary>,
-    chests: ResMut<Chests>,
-    gates: ResMut<Gates>,
+    mut ani_registry: ResMut<AniRegistry>,
+    ani_handles: Res<AniHandles>,
+    ani_sets: Res<Assets<AniSet>>,
+    mut chests: ResMut<Chests>,
+    mut gates: ResMut<Gates>,
     mut chunk_map: ResMut<ChunkMap>,
     map_config: Res<MapConfig>,
+    run_seed: Res<RunSeed>,
+    stage_seed: Res<StageSeed>,
 ) {
     println!("🔥 setup_scene");
 
+    // Clean up previous run
+    chests.0.clear();
+    gates.0.clear();
+
     // Load map
     // let (walkables, start, goal, map) = load_map_from_csv("assets/map.csv").unwrap();
     // *chunk_map = ChunkMap { walkables };
 
-    let pubkey = "gistmeAhMG7AcKSPCHis8JikGmKT9tRRyZpyMLNNULq";
-    let (walkables, start, goal, map, graves) = gen_map_from_public_key(pubkey).unwrap();
-    let mut walkables = walkables;
-    let mut map = map;
-
-    let (refined_game_map, refined_walkables) =
-        refine_walkable_map(&mut walkables, &mut map, &start, &goal);
-
-    *chunk_map = ChunkMap {
-        walkables: refined_walkables,
-        entrance: start.clone(),
-        exit: goal.clone(),
-        graves,
-    };
+    let (refined_game_map, generated) = gen_chunk_map(&stage_seed, &run_seed);
+    let start = generated.entrance.clone();
+    let goal = generated.exit.clone();
+    let exit_gate = generated.exit_gate.clone();
+    *chunk_map = generated;
 
     let chest_entities = build_scene(
         &mut commands,
         &asset_server,
         &mut atlas_layouts,
         &mut library,
+        &mut ani_registry,
+        &ani_sets
+            .get(&ani_handles.decors)
+            .expect("Expected decor.ani.json loaded")
+            .0,
         refined_game_map,
         chests,
         gates,
         start,
-        goal,
+        // Exit through the gate when there is one
+        exit_gate.unwrap_or(goal),
         map_config,
     );
 
@@ -62,3 +74,27 @@ pub fn setup_scene(
         commands.entity(entity).insert(chest.clone());
     });
 }
+
+// Same seeds, same map.
+pub fn gen_chunk_map(stage_seed: &StageSeed, run_seed: &RunSeed) -> (GameMap, ChunkMap) {
+    let (mut walkables, start, goal, mut map, graves) =
+        gen_map_from_public_key(&stage_seed.0, run_seed.0).expect("Expected valid public key");
+
+    let (refined_game_map, refined_walkables) =
+        refine_walkable_map(&mut walkables, &mut map, &start, &goal, run_seed.0);
+
+    // Gates next to 🆕 and 🆒
+    let entrance_gate = find_adjacent(&refined_game_map.0, &start, "🚪");
+    let exit_gate = find_adjacent(&refined_game_map.0, &goal, "🚪");
+
+    let chunk_map = ChunkMap {
+        walkables: refined_walkables,
+        entrance: start,
+        exit: goal,
+        entrance_gate,
+        exit_gate,
+        graves,
+    };
+
+    (refined_game_map, chunk_map)
+}
@@ -1,16 +1,26 @@
 use crate::{
-    animations::entities::AniType,
+    animations::{
+        entities::AniType,
+        loader::{AniHandles, AniSet},
+    },
+    brains::patrol::Waypoint,
     characters::{
         actions::{Act, LookDirection},
+        effect::{EffectTile, StatusEffect},
         entities::{CharacterId, CharacterKind},
     },
-    get_type_id,
+    core::{
+        faction::FactionTable,
+        grave::GraveDef,
+        lever::SwitchDef,
+        objective::{default_objectives, Objective},
+        state::{GameState, LoadError},
+    },
 };
 use anyhow::*;
 use bevy::prelude::*;
 use serde::Deserialize;
 use serde_yaml::from_str;
-use std::{fs, slice::Iter};
 
 #[allow(unused)]
 #[cfg_attr(feature = "bevy", derive(Resource))]
@@ -18,56 +28,73 @@ use std::{fs, slice::Iter};
 pub struct Stage {
     pub id: String,
     pub name: String,
-    pub humans: Vec<Human>,
-    pub enemies: Vec<Monster>,
-    pub npcs: Vec<Npc>,
+    // Stage after this one once cleared, e.g. "1-2"
+    #[serde(default)]
+    pub next: Option<String>,
+    pub humans: Vec<CharacterDef>,
+    pub enemies: Vec<CharacterDef>,
+    pub npcs: Vec<CharacterDef>,
+    #[serde(default)]
+    pub factions: FactionTable,
+    #[serde(default)]
+    pub graves: Vec<GraveDef>,
+    #[serde(default)]
+    pub tiles: Vec<EffectTile>,
+    #[serde(default)]
+    pub switches: Vec<SwitchDef>,
+    #[serde(
+        default = "default_objectives",
+        with = "serde_yaml::with::singleton_map_recursive"
+    )]
+    pub objectives: Vec<Objective>,
 }
 
-pub trait StageInfo {
-    fn get_characters_iter_by_type<T>(&self) -> Option<Iter<T>>
-    where
-        T: 'static;
-}
+impl Stage {
+    // Everyone in the stage, humans, enemies and npcs.
+    pub fn characters(&self) -> impl Iterator<Item = &CharacterDef> {
+        self.humans
+            .iter()
+            .chain(self.enemies.iter())
+            .chain(self.npcs.iter())
+    }
 
-impl StageInfo for Stage {
-    fn get_characters_iter_by_type<T>(&self) -> Option<Iter<T>>
-    where
-        T: 'static,
-    {
-        match get_type_id!(T) {
-            id if id == get_type_id!(Human) => {
-                let humans: &[T] = unsafe { std::mem::transmute(&self.humans[..]) };
-                Some(humans.iter())
-            }
-            id if id == get_type_id!(Monster) => {
-                let enemies: &[T] = unsafe { std::mem::transmute(&self.enemies[..]) };
-                Some(enemies.iter())
+    // Every spawned character need a sheet in char.ani.json.
+    pub fn validate_ani_types(&self, known_ani_types: &[AniType]) -> Result<()> {
+        for character in self.characters() {
+            if !known_ani_types.contains(&character.ani_type) {
+                let known = known_ani_types
+                    .iter()
+                    .map(|ani_type| ani_type.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                bail!(
+                    "Unknown ani_type \"{}\" for {} in stage {}, expected one of: {}",
+                    character.ani_type,
+                    character.character_id.0,
+                    self.id,
+                    known
+                );
             }
-            id if id == get_type_id!(Npc) => {
-                let npcs: &[T] = unsafe { std::mem::transmute(&self.npcs[..]) };
-                Some(npcs.iter())
-            }
-            _ => None,
         }
+
+        Ok(())
     }
 }
 
-pub trait CharacterInfo: Component {
-    fn kind(&self) -> &CharacterKind;
-    fn ani_type(&self) -> &AniType;
-    fn character_id(&self) -> &CharacterId;
-    fn position(&self) -> &String;
-    fn look_direction(&self) -> &LookDirection;
-    fn act(&self) -> &Act;
-    fn get_clone(&self) -> Self;
-    fn line_of_sight(&self) -> f32;
-    fn attack(&self) -> u32;
+pub fn default_attack_cooldown() -> f32 {
+    1.0
 }
 
+pub fn default_health() -> u32 {
+    100
+}
+
+// Any character in a stage, `kind` decide who is hostile to who.
 #[allow(unused)]
 #[cfg_attr(feature = "bevy", derive(Component))]
 #[derive(Deserialize, Default, Clone, Debug)]
-pub struct Human {
+#[serde(default)]
+pub struct CharacterDef {
     pub kind: CharacterKind,
     pub ani_type: AniType,
     pub character_id: CharacterId,
@@ -77,161 +104,153 @@ pub struct Human {
     pub line_of_sight: f32,
     pub attack: u32,
     pub defend: u32,
+    // Seconds between hits
+    #[serde(default = "default_attack_cooldown")]
+    pub attack_cooldown: f32,
+    #[serde(default = "default_health")]
     pub health: u32,
+    // Spent by attacks and walking, none when 0
+    pub stamina: u32,
+    // Lowered by hits and fallen allies, flee when low, none when 0
+    pub morale: u32,
     pub tasks: Vec<String>,
     pub mindsets: Vec<String>,
+    // Waypoints to loop over when idle
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub patrol: Vec<Waypoint>,
+    // Given to the party on death
+    pub drops: Vec<String>,
+    // Given to whoever get hit, e.g. poison
+    pub on_hit: Vec<StatusEffect>,
+    // For npcs to talk
+    pub prompt: Option<String>,
 }
 
-impl CharacterInfo for Human {
-    fn kind(&self) -> &CharacterKind {
-        &self.kind
-    }
-    fn ani_type(&self) -> &AniType {
-        &self.ani_type
-    }
-    fn character_id(&self) -> &CharacterId {
-        &self.character_id
-    }
-    fn position(&self) -> &String {
-        &self.position
-    }
-    fn look_direction(&self) -> &LookDirection {
-        &self.look_direction
-    }
-    fn act(&self) -> &Act {
-        &self.act
-    }
-    fn get_clone(&self) -> Self {
-        self.clone()
-    }
-    fn line_of_sight(&self) -> f32 {
-        self.line_of_sight
-    }
-    fn attack(&self) -> u32 {
-        self.attack
-    }
-}
+#[cfg_attr(feature = "bevy", derive(Resource))]
+#[derive(Default, Debug)]
+pub struct GameStage(pub Stage);
 
-#[allow(unused)]
-#[cfg_attr(feature = "bevy", derive(Component))]
-#[derive(Deserialize, Clone, Debug)]
-pub struct Monster {
-    pub kind: CharacterKind,
-    pub ani_type: AniType,
-    pub character_id: CharacterId,
-    pub position: String,
-    pub look_direction: LookDirection,
-    pub act: Act,
-    pub line_of_sight: f32,
-    pub attack: u32,
-    pub defend: u32,
-    pub health: u32,
-    pub mindsets: Vec<String>,
-}
+// Stage to play, e.g. "1-1" for assets/stage_1-1.yml.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct StageId(pub String);
 
-impl CharacterInfo for Monster {
-    fn kind(&self) -> &CharacterKind {
-        &self.kind
-    }
-    fn ani_type(&self) -> &AniType {
-        &self.ani_type
-    }
-    fn character_id(&self) -> &CharacterId {
-        &self.character_id
-    }
-    fn position(&self) -> &String {
-        &self.position
-    }
-    fn look_direction(&self) -> &LookDirection {
-        &self.look_direction
-    }
-    fn act(&self) -> &Act {
-        &self.act
-    }
-    fn get_clone(&self) -> Self {
-        self.clone()
-    }
-    fn line_of_sight(&self) -> f32 {
-        self.line_of_sight
-    }
-    fn attack(&self) -> u32 {
-        self.attack
+impl Default for StageId {
+    fn default() -> Self {
+        Self("1-1".to_string())
     }
 }
 
-#[allow(unused)]
-#[cfg_attr(feature = "bevy", derive(Component))]
-#[derive(Deserialize, Debug)]
-pub struct Npc {
-    pub kind: CharacterKind,
-    pub ani_type: AniType,
-    pub character_id: CharacterId,
-    pub position: String,
-    pub look_direction: LookDirection,
-    pub act: Act,
-    pub prompt: String,
+impl StageId {
+    pub fn get_path(&self) -> String {
+        format!("assets/stage_{}.yml", self.0)
+    }
 }
 
-#[cfg_attr(feature = "bevy", derive(Resource))]
-#[derive(Default, Debug)]
-pub struct GameStage(pub Stage);
+// Wasm has no fs, stages are baked in by path.
+#[cfg(target_arch = "wasm32")]
+const EMBEDDED_STAGES: &[(&str, &str)] = &[
+    (
+        "assets/stage_1-1.yml",
+        include_str!("../../assets/stage_1-1.yml"),
+    ),
+    (
+        "assets/stage_1-2.yml",
+        include_str!("../../assets/stage_1-2.yml"),
+    ),
+];
 
 pub fn load_stage_from_yaml(file_path: &str) -> Result<Stage> {
-    // let file_content = fs::read_to_string(file_path).expect("Expected stage_1-1.yml");
-    let file_content = r#"id: 1-1
-name: "hello world!"
-humans:
-  - kind: human
-    ani_type: man
-    character_id: man_0
-    position: e2
-    look_direction: left
-    act: idle
-    line_of_sight: 200
-    attack: 10
-    defend: 10
-    health: 100
-    tasks:
-      - "find the chest"
-    mindsets:
-      - "when idle, do task"
-      - "when no task, find exit"
-      - "when beside(<1 block) monster, attack monster"
-      - "when lost, find unvisited place"
-      - "when hurt, attack the attacker"
-      - "when low(<50%) health and has potion, drink potion"
-enemies:
-  - kind: monster
-    ani_type: skeleton
-    character_id: skeleton_0
-    position: f3
-    look_direction: right
-    act: idle
-    line_of_sight: 100
-    attack: 1
-    defend: 10
-    health: 100
-    mindsets:
-      - "when idle, rally between grave and chest"
-      - "when near (<2 block) player, follow player"
-      - "when beside (<1 block) player, attack player"
-npcs:
-  - kind: animal
-    ani_type: crab
-    character_id: crab_0
-    position: b2
-    look_direction: left
-    act: idle
-    prompt: |
-      You are a crab representing Rustaceans. 
-      Say only good things about Rust language, 
-      Nothing else.
-"#;
+    #[cfg(not(target_arch = "wasm32"))]
+    let file_content = std::fs::read_to_string(file_path)
+        .with_context(|| format!("Expected {} on disk", file_path))?;
+
+    #[cfg(target_arch = "wasm32")]
+    let file_content = EMBEDDED_STAGES
+        .iter()
+        .find(|(path, _)| *path == file_path)
+        .map(|(_, content)| content.to_string())
+        .with_context(|| format!("Expected {} embedded", file_path))?;
+
     let stage: Stage = from_str(&file_content)?;
     Ok(stage)
 }
 
-pub fn init_stage(mut commands: Commands) {
-    let stage = load_stage_from_yaml("assets/stage_1-1.yml").expect("stage_1-1.yml");
-    commands.insert_resource(GameStage(stage));
+// A broken stage sends the player back to the menu with the error.
+pub fn init_stage(
+    mut commands: Commands,
+    ani_handles: Res<AniHandles>,
+    ani_sets: Res<Assets<AniSet>>,
+    stage_id: Res<StageId>,
+    mut load_error: ResMut<LoadError>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let known_ani_types = ani_sets
+        .get(&ani_handles.characters)
+        .map(|ani_set| {
+            ani_set
+                .0
+                .iter()
+                .map(|ani| ani.ani_type.clone())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let stage = load_stage_from_yaml(&stage_id.get_path()).and_then(|stage| {
+        stage.validate_ani_types(&known_ani_types)?;
+        Ok(stage)
+    });
+
+    match stage {
+        Result::Ok(stage) => {
+            commands.insert_resource(stage.factions.clone());
+            commands.insert_resource(GameStage(stage));
+        }
+        Err(err) => {
+            println!("🗺 Can't load stage: {:#}", err);
+            load_error.0 = Some(format!("{:#}", err));
+            game_state.set(GameState::Menu);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_stage_1_1() {
+        let stage = load_stage_from_yaml("assets/stage_1-1.yml").unwrap();
+
+        // Sections added over time, they have to reach the game.
+        assert!(!stage.factions.0.is_empty());
+        assert!(stage
+            .humans
+            .iter()
+            .any(|human| human.character_id == CharacterId("man_1".to_string())));
+        assert!(!stage.graves.is_empty());
+        assert!(!stage.tiles.is_empty());
+        assert_eq!(stage.switches[0].gate, "exit");
+
+        let skeleton = &stage.enemies[0];
+        assert!(!skeleton.patrol.is_empty());
+        assert!(!skeleton.drops.is_empty());
+        assert!(!skeleton.on_hit.is_empty());
+        assert!(stage.humans[0].stamina > 0);
+        assert!(stage.humans[0].morale > 0);
+    }
+
+    #[test]
+    fn test_load_next_stage() {
+        let stage = load_stage_from_yaml("assets/stage_1-1.yml").unwrap();
+        let next = StageId(stage.next.unwrap());
+        assert_eq!(next.get_path(), "assets/stage_1-2.yml");
+
+        let next_stage = load_stage_from_yaml(&next.get_path()).unwrap();
+        assert_eq!(next_stage.id, next.0);
+        assert_eq!(StageId::default().get_path(), "assets/stage_1-1.yml");
+    }
+
+    #[test]
+    fn test_load_missing_stage() {
+        assert!(load_stage_from_yaml("assets/stage_0-0.yml").is_err());
+    }
 }
@@ -1,11 +1,19 @@
-use bevy::prelude::States;
+use bevy::prelude::{Resource, States};
 
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
 pub enum GameState {
     #[default]
     Splash,
     Menu,
+    // Wait for assets before the game
+    Loading,
     Game,
+    // Play back the last run
+    Replay,
     Clear,
     Over,
 }
+
+// Why the last load failed, e.g. a broken char.ani.json, shown on the main menu.
+#[derive(Resource, Default, Debug)]
+pub struct LoadError(pub Option<String>);
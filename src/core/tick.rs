@@ -0,0 +1,272 @@
+use bevy::prelude::*;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::characters::{actions::Action, bar::Health, entities::CharacterId};
+
+use super::position::{Position, PreviousPosition};
+
+// Gameplay run at this many ticks per second whatever the frame rate.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct TickRate(pub f64);
+
+impl Default for TickRate {
+    fn default() -> Self {
+        Self(30.)
+    }
+}
+
+pub fn apply_tick_rate(tick_rate: Res<TickRate>, mut fixed_time: ResMut<Time<Fixed>>) {
+    if tick_rate.is_changed() {
+        fixed_time.set_timestep_hz(tick_rate.0);
+    }
+}
+
+// Before each tick, see `get_interpolated_xy`.
+pub fn store_previous_position(mut query: Query<(&Position, &mut PreviousPosition)>) {
+    for (position, mut previous) in query.iter_mut() {
+        previous.0 = position.xy;
+    }
+}
+
+// Between the last two ticks, by how far the frame is into the next one.
+pub fn get_interpolated_xy(
+    position: &Position,
+    previous: Option<&PreviousPosition>,
+    fixed_time: &Time<Fixed>,
+) -> Vec2 {
+    match previous {
+        Some(previous) => previous.0.lerp(position.xy, fixed_time.overstep_fraction()),
+        None => position.xy,
+    }
+}
+
+// Same seed, same hash, to compare two runs.
+// Every character, corpses have no `Position` left.
+pub fn hash_world(world: &mut World) -> u64 {
+    let mut query = world.query::<(&CharacterId, &Action, Option<&Position>, Option<&Health>)>();
+    let mut states = query
+        .iter(world)
+        .map(|(character_id, action, position, health)| {
+            (
+                character_id.0.clone(),
+                action.0.to_string(),
+                position.map(|position| (position.xy.x.to_bits(), position.xy.y.to_bits())),
+                health.map(|health| health.value.to_bits()),
+            )
+        })
+        .collect::<Vec<_>>();
+    states.sort();
+
+    let mut hasher = DefaultHasher::new();
+    states.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn log_world_hash(world: &mut World) {
+    println!("🎲 World hash:{:x}", hash_world(world));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        afterlife::stats::RunStats,
+        animations::{build::AniRegistry, entities::Ani},
+        brains::{
+            avoid::{avoid_action_system, avoid_scorer_system},
+            fight::{fight_action_system, fight_scorer_system, fight_system},
+            loot::{loot_action_system, loot_scorer_system, loot_system},
+            order::{follow_order_action_system, order_scorer_system},
+            party::{update_party_knowledge, Party},
+            patrol::follow_patrol_action_system,
+            thinker::{
+                guard_action_system, guard_system, guarding_scorer_system, move_to_foe_system,
+                move_to_nearest_system,
+            },
+        },
+        characters::{
+            bar::{apply_stat_rate, HealthValue, MoraleValue, StaminaValue},
+            builder::{get_character_cell, insert_character},
+            effect::{apply_effect_events, update_status_effects, EffectEvent},
+            stats::{spend_stamina_on_attack, update_morale, update_stamina_rate},
+            update::{update_attack_cooldown, update_swing},
+        },
+        core::{
+            chest::{Chest, ChestId, ChestState, Chests},
+            gate::Gates,
+            lever::update_pressure_plate,
+            map::get_position_from_map,
+            objective::ObjectiveTracker,
+            point::Exit,
+            seed::{RunSeed, StageSeed},
+            setup::gen_chunk_map,
+            stage::load_stage_from_yaml,
+        },
+        interactions::{
+            damage::{update_damage, DamageEvent, DamageRng, HitEvent},
+            death::{handle_death, DeathEvent, Inventory},
+            toggle::{update_toggle, Interactable, ToggleEvent},
+        },
+    };
+    use bevy::time::TimeUpdateStrategy;
+    use big_brain::{BigBrainPlugin, BigBrainSet};
+    use std::time::Duration;
+
+    // The tick of `game_plugin` on a generated stage 1-1, without sprites.
+    // Grave spawners and objectives are not in here, they need assets and states.
+    fn run(seed: u64, ticks: usize) -> u64 {
+        let stage = load_stage_from_yaml("assets/stage_1-1.yml").unwrap();
+        let anis: Vec<Ani> =
+            serde_json::from_slice(&std::fs::read("assets/char.ani.json").unwrap()).unwrap();
+        let (game_map, chunk_map) = gen_chunk_map(&StageSeed::default(), &RunSeed(seed));
+
+        let tick_rate = TickRate::default();
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, BigBrainPlugin::new(FixedUpdate)))
+            .insert_resource(tick_rate)
+            .insert_resource(Time::<Fixed>::from_hz(tick_rate.0))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+                1. / tick_rate.0,
+            )))
+            .insert_resource(DamageRng::from(&RunSeed(seed)))
+            .insert_resource(stage.factions.clone())
+            .insert_resource(chunk_map.clone())
+            .init_resource::<RunStats>()
+            .init_resource::<AniRegistry>()
+            .init_resource::<Chests>()
+            .init_resource::<Gates>()
+            .init_resource::<ObjectiveTracker>()
+            .init_resource::<Party>()
+            .init_resource::<Inventory>()
+            .add_event::<DamageEvent>()
+            .add_event::<HitEvent>()
+            .add_event::<DeathEvent>()
+            .add_event::<EffectEvent>()
+            .add_event::<ToggleEvent>()
+            .add_systems(FixedFirst, store_previous_position)
+            .add_systems(
+                FixedUpdate,
+                (
+                    guard_system,
+                    (update_pressure_plate, update_toggle)
+                        .chain()
+                        .after(update_swing),
+                    (update_attack_cooldown, update_swing).chain(),
+                    (update_party_knowledge, loot_system::<Chest>).chain(),
+                    fight_system,
+                    (update_damage, handle_death).chain().after(update_swing),
+                    (
+                        apply_effect_events,
+                        update_status_effects,
+                        apply_stat_rate::<HealthValue>,
+                    )
+                        .chain()
+                        .after(update_damage),
+                    (
+                        update_stamina_rate,
+                        spend_stamina_on_attack,
+                        apply_stat_rate::<StaminaValue>,
+                        update_morale,
+                        apply_stat_rate::<MoraleValue>,
+                    )
+                        .chain()
+                        .after(update_damage),
+                )
+                    .after(BigBrainSet::Actions),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    guard_action_system::<Chest>,
+                    follow_order_action_system,
+                    follow_patrol_action_system,
+                    avoid_action_system,
+                    move_to_nearest_system::<Exit>,
+                    fight_scorer_system,
+                    move_to_foe_system,
+                    fight_action_system,
+                    loot_scorer_system,
+                    move_to_nearest_system::<Chest>,
+                    loot_action_system::<Chest>,
+                )
+                    .in_set(BigBrainSet::Actions),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    guarding_scorer_system,
+                    order_scorer_system,
+                    avoid_scorer_system,
+                )
+                    .in_set(BigBrainSet::Scorers),
+            );
+
+        // Chests and exit, like `build_scene`
+        for (y, row) in game_map.0.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if cell != "💰" {
+                    continue;
+                }
+                let chest_id = format!("chest_{}", app.world().resource::<Chests>().0.len());
+                let chest = Chest {
+                    status: ChestState::Close,
+                    key: None,
+                };
+                let xy = get_position_from_map(x, y, None).translation.xy();
+                app.world_mut().spawn((
+                    chest.clone(),
+                    ChestId(chest_id.clone()),
+                    Interactable::Chest,
+                    Position { xy },
+                ));
+                app.world_mut()
+                    .resource_mut::<Chests>()
+                    .0
+                    .insert(chest_id, chest);
+            }
+        }
+        let exit = chunk_map
+            .exit_gate
+            .clone()
+            .unwrap_or(chunk_map.exit.clone());
+        app.world_mut().spawn((
+            Exit,
+            Position {
+                xy: get_position_from_map(exit.x, exit.y, None).translation.xy(),
+            },
+        ));
+
+        // Characters, like `init_characters`
+        for character in stage.characters() {
+            let ani = anis
+                .iter()
+                .find(|ani| ani.ani_type == character.ani_type)
+                .unwrap();
+            let Some((x, y)) = get_character_cell(character, &chunk_map) else {
+                continue;
+            };
+            let at = get_position_from_map(x, y, None).translation.xy();
+
+            let mut commands = app.world_mut().commands();
+            let mut entity_commands = commands.spawn(Sprite::default());
+            insert_character(&mut entity_commands, ani, character, at);
+        }
+        app.world_mut().flush();
+
+        for _ in 0..ticks {
+            app.update();
+        }
+
+        hash_world(app.world_mut())
+    }
+
+    #[test]
+    fn test_same_seed_same_world() {
+        assert_eq!(run(42, 300), run(42, 300));
+    }
+
+    #[test]
+    fn test_world_changes_over_ticks() {
+        assert_ne!(run(42, 1), run(42, 300));
+    }
+}
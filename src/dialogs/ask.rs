@@ -1,6 +1,8 @@
 use bevy::{asset::AssetServer, prelude::*};
 
-use crate::{characters::entities::CharacterId, core::layer::SpriteLayer};
+use crate::{
+    characters::entities::CharacterId, core::layer::SpriteLayer, entry::game::OnGameScreen,
+};
 
 #[allow(unused)]
 #[derive(Clone, Default, Debug, PartialEq)]
@@ -25,6 +27,7 @@ struct AskDialogBundle {
     node_bundle: NodeBundle,
     sprite_layer: SpriteLayer,
     dialog: AskDialog,
+    screen: OnGameScreen,
 }
 
 #[allow(clippy::type_complexity)]
@@ -89,21 +92,25 @@ fn show_ask_dialog(
         sprite_layer: SpriteLayer::Ui,
         dialog: AskDialog {
             by: ask_dialog_content.by,
-            content: ask_dialog_content.content,
+            content: ask_dialog_content.content.clone(),
         },
+        screen: OnGameScreen,
     });
 
     commands
-        .spawn(NodeBundle {
-            style: Style {
-                width: Val::Percent(100.0),
-                height: Val::Percent(100.0),
-                align_items: AlignItems::Center,
-                justify_content: JustifyContent::Center,
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
                 ..default()
             },
-            ..default()
-        })
+            OnGameScreen,
+        ))
         .with_children(|parent| {
             {
                 let [w, h] = [320.0, 64.0];
@@ -127,7 +134,7 @@ fn show_ask_dialog(
                     ))
                     .with_children(|parent| {
                         parent.spawn(TextBundle::from_section(
-                            "STAGE CLEAR!",
+                            ask_dialog_content.content,
                             TextStyle {
                                 font: asset_server.load("PixelOperator-Bold.ttf"),
                                 font_size: 40.0,
@@ -1,54 +1,88 @@
+use std::time::Duration;
+
 use bevy::{
     color::palettes::basic::{BLUE, LIME},
     prelude::*,
+    time::common_conditions::on_timer,
 };
 
 use crate::{
-    afterlife, brains, characters,
+    afterlife, animations, brains, characters,
     core::{self, map::MapConfig},
     dialogs, entry, interactions, Configuration,
 };
 
 use super::{despawn_screen, TEXT_COLOR};
 
-use afterlife::over::game_over_system;
+use afterlife::stats::{reset_run_stats, RunStats};
+use animations::{
+    build::AniRegistry,
+    loader::{check_ani_assets, load_ani_assets, reload_ani_assets, AniLoader, AniSet},
+};
 use bevy::{
     asset::AssetMetaCheck,
     log::LogPlugin,
     prelude::*,
     window::{PresentMode, WindowResolution},
 };
-use bevy_pkv::PkvStore;
 use bevy_spritesheet_animation::prelude::*;
 use bevy_stat_bars::RegisterStatbarSubject;
 use big_brain::{BigBrainPlugin, BigBrainSet};
 use brains::{
+    avoid::{avoid_action_system, avoid_scorer_system},
     behavior::Behavior,
     fight::{fight_action_system, fight_scorer_system, fight_system},
     loot::{loot_action_system, loot_scorer_system, loot_system},
+    order::{follow_order_action_system, order_scorer_system},
+    party::{reset_party, update_party_knowledge, Party},
+    patrol::follow_patrol_action_system,
     thinker::*,
 };
-use characters::{bar::Health, builder::init_character, update::update_character};
+use characters::{
+    bar::{
+        apply_stat_rate, update_stat_bar, Health, HealthValue, Morale, MoraleValue, Stamina,
+        StaminaValue,
+    },
+    builder::init_characters,
+    control::{direct_control_system, toggle_direct_control_system},
+    effect::{
+        apply_effect_events, drink_potion, update_effect_icons, update_effect_tiles,
+        update_status_effects, EffectEvent,
+    },
+    hud::{spawn_hud, update_hero_hud},
+    stats::{spend_stamina_on_attack, update_morale, update_stamina_rate},
+    update::{update_attack_cooldown, update_character, update_swing},
+};
 use core::{
     chest::{update_chest, Chest, Chests},
-    gate::{update_gate, Gates},
-    grave::Grave,
+    faction::FactionTable,
+    gate::{open_entrance_gate, open_exit_gate, update_gate, Gates},
+    grave::{init_grave_spawners, update_grave_spawners, update_sealed_grave},
     layer::{y_sort, SpriteLayer},
+    lever::{init_switches, update_lever, update_pressure_plate},
     menu::button_system,
+    objective::{init_objectives, update_objective_hud, update_objectives, ObjectiveTracker},
     point::Exit,
+    save::{apply_save, delete_save, save_game, spawn_saved_risen, PendingSave, AUTOSAVE_STEP},
     scene::{ChunkMap, MainPath},
+    seed::{receive_public_key, RunSeed},
     setup::setup_scene,
-    stage::{init_stage, GameStage, Human, Monster},
+    stage::{init_stage, GameStage, StageId},
     state::GameState,
+    tick::{apply_tick_rate, log_world_hash, store_previous_position, TickRate},
 };
 use dialogs::ask::{update_ask_dialog, AskDialogEvent};
-use entry::{game, menu, splash, DisplayQuality, Volume};
+use entry::{game, menu, settings::has_effects, splash, DisplayQuality, Volume};
 use extol_sprite_layer::SpriteLayerPlugin;
 use interactions::{
     damage::{
-        despawn_damage_indicator, spawn_damage_indicator, update_damage, DamageEvent, Damages,
+        despawn_damage_indicator, reset_damage_rng, spawn_damage_indicator, spawn_damage_number,
+        spawn_hurt_tint, update_damage, update_damage_number, update_hurt_tint, DamageEvent,
+        DamageRng, Damages, HitEvent,
     },
-    toggle::{update_toggle_chest, ToggleEvent},
+    death::{handle_death, reset_inventory, update_corpse, DeathEvent, Inventory},
+    pick::pick_cell_system,
+    toggle::{update_toggle, ToggleEvent},
 };
 
 // This plugin will contain the game. In this case, it's just be a screen that will
@@ -58,103 +92,209 @@ pub fn game_plugin(app: &mut App) {
         SpritesheetAnimationPlugin,
         SpriteLayerPlugin::<SpriteLayer>::default(),
     ))
-    .add_plugins(BigBrainPlugin::new(PreUpdate))
+    .add_plugins(BigBrainPlugin::new(FixedUpdate))
     // .add_plugins(ResourceInspectorPlugin::<Configuration>::default())
     .register_type::<Health>()
+    .register_type::<Stamina>()
+    .register_type::<Morale>()
     .register_type::<Behavior>()
     .add_statbar_component_observer::<Health>()
-    .insert_resource(PkvStore::new("foo", "bar"))
+    .add_statbar_component_observer::<Stamina>()
+    .add_statbar_component_observer::<Morale>()
+    .init_asset::<AniSet>()
+    .init_asset_loader::<AniLoader>()
+    .init_resource::<AniRegistry>()
     .init_resource::<Configuration>()
     .init_resource::<Chests>()
     .init_resource::<Gates>()
     .init_resource::<ChunkMap>()
     .init_resource::<MainPath>()
     .init_resource::<GameStage>()
+    .init_resource::<StageId>()
     .init_resource::<Damages>()
     .init_resource::<MapConfig>()
+    .init_resource::<ObjectiveTracker>()
+    .init_resource::<RunStats>()
+    .init_resource::<RunSeed>()
+    .init_resource::<DamageRng>()
+    .init_resource::<FactionTable>()
+    .init_resource::<Party>()
+    .init_resource::<Inventory>()
+    .init_resource::<TickRate>()
+    .init_resource::<PendingSave>()
+    .add_systems(Startup, load_ani_assets)
+    .add_systems(
+        Update,
+        (
+            check_ani_assets.run_if(in_state(GameState::Loading)),
+            reload_ani_assets,
+            receive_public_key,
+        ),
+    )
     .add_systems(
         OnEnter(GameState::Game),
         ((
             setup_scene,
+            open_entrance_gate,
             init_stage,
-            init_character::<Human>,
-            init_character::<Monster>,
+            init_switches,
+            init_grave_spawners,
+            init_objectives,
+            reset_run_stats,
+            reset_damage_rng,
+            reset_party,
+            reset_inventory,
+            init_characters,
+            spawn_saved_risen,
+            apply_save,
+            spawn_hud,
         )
             .chain(),),
     )
+    // Frame rate side: input, animation, feedback and UI.
     .add_systems(
         Update,
         (
             y_sort,
             // adjust_stats,
             button_system,
-            guard_system,
-            // Chest
-            update_chest,
-            update_toggle_chest,
-            update_gate,
+            // Interactable
+            (update_chest, update_gate, update_sealed_grave),
             // Character
-            update_character::<Human>,
-            update_character::<Monster>,
-            // Loot
-            loot_system::<Human, Chest>,
-            // Fight
-            fight_system::<Monster, Human>,
-            fight_system::<Human, Monster>,
+            update_character,
+            // Control
+            (toggle_direct_control_system, pick_cell_system),
             // Damage
-            spawn_damage_indicator,
-            update_damage,
-            // // Die
-            // despawn_fighter_on_death_system::<Human>,
-            // despawn_fighter_on_death_system::<Monster>,
-            despawn_damage_indicator,
-            // death_system,
+            (
+                spawn_damage_indicator,
+                despawn_damage_indicator,
+                update_corpse,
+                // Feedback
+                (
+                    spawn_damage_number.run_if(has_effects),
+                    update_damage_number,
+                    spawn_hurt_tint.run_if(has_effects),
+                    update_hurt_tint,
+                ),
+            ),
+            update_effect_icons,
             update_ask_dialog,
+            update_objective_hud,
+            // Bars, HUD
+            (
+                update_stat_bar::<HealthValue>,
+                update_stat_bar::<StaminaValue>,
+                update_stat_bar::<MoraleValue>,
+                update_hero_hud,
+            ),
         )
             .run_if(in_state(GameState::Game)),
     )
     .add_systems(
         Update,
-        (update_ask_dialog,).run_if(in_state(GameState::Clear)),
+        save_game.run_if(
+            in_state(GameState::Game).and_then(on_timer(Duration::from_secs_f32(AUTOSAVE_STEP))),
+        ),
     )
+    // Tick side: everything that decide the outcome of a run.
+    .add_systems(Update, apply_tick_rate)
+    .add_systems(FixedFirst, store_previous_position)
     .add_systems(
-        Update,
-        (game_over_system,).run_if(in_state(GameState::Game)),
+        FixedUpdate,
+        (
+            guard_system,
+            // Interactable
+            (
+                (update_pressure_plate, update_toggle).chain(),
+                open_exit_gate,
+                update_lever,
+                update_grave_spawners,
+            )
+                .after(update_swing),
+            // Character, hits land on the tick
+            (update_attack_cooldown, direct_control_system, update_swing).chain(),
+            // Party, loot
+            (update_party_knowledge, loot_system::<Chest>).chain(),
+            // Fight
+            fight_system,
+            // Damage, die
+            (update_damage, handle_death).chain().after(update_swing),
+            // Status effects
+            (
+                update_effect_tiles,
+                drink_potion,
+                apply_effect_events,
+                update_status_effects,
+                apply_stat_rate::<HealthValue>,
+            )
+                .chain()
+                .after(update_damage),
+            // Stamina, morale
+            (
+                update_stamina_rate,
+                spend_stamina_on_attack,
+                apply_stat_rate::<StaminaValue>,
+                update_morale,
+                apply_stat_rate::<MoraleValue>,
+            )
+                .chain()
+                .after(update_damage),
+            // Objective
+            update_objectives.after(handle_death),
+        )
+            .after(BigBrainSet::Actions)
+            .run_if(in_state(GameState::Game)),
     )
     .add_systems(
-        PreUpdate,
+        FixedUpdate,
         (
             guard_action_system::<Chest>,
-            move_to_nearest_system::<Grave>,
+            // --- Player Order ---
+            follow_order_action_system,
+            // --- Patrol ---
+            follow_patrol_action_system,
+            // --- Avoid ---
+            avoid_action_system,
             move_to_nearest_system::<Exit>,
-            // --- Monster Fight ---
-            // Monster seek for Human
-            fight_scorer_system::<Monster>,
-            // Monster follow Human
-            move_to_nearest_system::<Human>,
-            // Monster fight with Human
-            fight_action_system::<Monster, Human>,
-            // --- Human Fight ---
-            // Human seek for Monster
-            fight_scorer_system::<Human>,
-            // Human follow Monster
-            move_to_nearest_system::<Monster>,
-            // Human fight with Monster
-            fight_action_system::<Human, Monster>,
-            // --- Human Loot ---
-            loot_scorer_system::<Human>,
+            // --- Fight ---
+            // Seek for a foe
+            fight_scorer_system,
+            // Follow the foe
+            move_to_foe_system,
+            // Fight with the foe
+            fight_action_system,
+            // --- Loot ---
+            loot_scorer_system,
             move_to_nearest_system::<Chest>,
-            loot_action_system::<Human, Chest>,
+            loot_action_system::<Chest>,
         )
             .in_set(BigBrainSet::Actions)
             .run_if(in_state(GameState::Game)),
     )
-    .add_systems(First, guarding_scorer_system)
+    .add_systems(
+        FixedUpdate,
+        (
+            guarding_scorer_system,
+            order_scorer_system,
+            avoid_scorer_system,
+        )
+            .in_set(BigBrainSet::Scorers)
+            .run_if(in_state(GameState::Game)),
+    )
     .add_event::<DamageEvent>()
+    .add_event::<HitEvent>()
+    .add_event::<DeathEvent>()
+    .add_event::<EffectEvent>()
     .add_event::<ToggleEvent>()
     .add_event::<AskDialogEvent>()
     // .add_systems(Update, game.run_if(in_state(GameState::Game)))
-    .add_systems(OnExit(GameState::Game), despawn_screen::<OnGameScreen>);
+    .add_systems(
+        OnExit(GameState::Game),
+        (log_world_hash, save_game, despawn_screen::<OnGameScreen>).chain(),
+    )
+    // Nothing to continue once the run is over
+    .add_systems(OnEnter(GameState::Clear), delete_save)
+    .add_systems(OnEnter(GameState::Over), delete_save);
 }
 
 // Tag component used to tag entities added on the game screen
@@ -1,13 +1,22 @@
 use bevy::{app::AppExit, color::palettes::css::CRIMSON, prelude::*};
+use bevy_pkv::PkvStore;
 
-use crate::core::state::GameState;
+use crate::core::{
+    save::{load_game, PendingSave},
+    seed::{RunSeed, StageSeed},
+    stage::StageId,
+    state::{GameState, LoadError},
+};
 
-use super::{despawn_screen, DisplayQuality, Volume, TEXT_COLOR};
+use super::{
+    despawn_screen, DisplayQuality, GameSpeed, KeyBindings, Language, MusicVolume, SfxVolume,
+    Volume, TEXT_COLOR,
+};
 
-// This plugin manages the menu, with 5 different screens:
-// - a main menu with "New Game", "Settings", "Quit"
-// - a settings menu with two submenus and a back button
-// - two settings screen with a setting that can be set and a back button
+// This plugin manages the menu, with 6 different screens:
+// - a main menu with "Continue" (when saved), "New Game", "Settings", "Quit"
+// - a settings menu with three submenus and a back button
+// - three settings screen with settings that can be set and a back button
 pub fn menu_plugin(app: &mut App) {
     app
         // At start, the menu is not enabled. This will be changed in `menu_setup` when
@@ -41,12 +50,32 @@ pub fn menu_plugin(app: &mut App) {
         .add_systems(OnEnter(MenuState::SettingsSound), sound_settings_menu_setup)
         .add_systems(
             Update,
-            setting_button::<Volume>.run_if(in_state(MenuState::SettingsSound)),
+            (
+                setting_button::<Volume>,
+                setting_button::<MusicVolume>,
+                setting_button::<SfxVolume>,
+            )
+                .run_if(in_state(MenuState::SettingsSound)),
         )
         .add_systems(
             OnExit(MenuState::SettingsSound),
             despawn_screen::<OnSoundSettingsMenuScreen>,
         )
+        // Systems to handle the game settings screen
+        .add_systems(OnEnter(MenuState::SettingsGame), game_settings_menu_setup)
+        .add_systems(
+            Update,
+            (
+                setting_button::<Language>,
+                setting_button::<GameSpeed>,
+                setting_button::<KeyBindings>,
+            )
+                .run_if(in_state(MenuState::SettingsGame)),
+        )
+        .add_systems(
+            OnExit(MenuState::SettingsGame),
+            despawn_screen::<OnGameSettingsMenuScreen>,
+        )
         // Common systems to all screens that handles buttons behavior
         .add_systems(
             Update,
@@ -61,6 +90,7 @@ enum MenuState {
     Settings,
     SettingsDisplay,
     SettingsSound,
+    SettingsGame,
     #[default]
     Disabled,
 }
@@ -81,7 +111,11 @@ struct OnDisplaySettingsMenuScreen;
 #[derive(Component)]
 struct OnSoundSettingsMenuScreen;
 
-const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
+// Tag component used to tag entities added on the game settings menu screen
+#[derive(Component)]
+struct OnGameSettingsMenuScreen;
+
+pub const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
 const HOVERED_PRESSED_BUTTON: Color = Color::srgb(0.25, 0.65, 0.25);
 const PRESSED_BUTTON: Color = Color::srgb(0.35, 0.75, 0.35);
@@ -93,17 +127,19 @@ struct SelectedOption;
 // All actions that can be triggered from a button click
 #[derive(Component)]
 enum MenuButtonAction {
+    Continue,
     Play,
     Settings,
     SettingsDisplay,
     SettingsSound,
+    SettingsGame,
     BackToMainMenu,
     BackToSettings,
     Quit,
 }
 
 // This system handles changing all buttons color based on mouse interaction
-fn button_system(
+pub fn button_system(
     mut interaction_query: Query<
         (&Interaction, &mut UiImage, Option<&SelectedOption>),
         (Changed<Interaction>, With<Button>),
@@ -120,18 +156,20 @@ fn button_system(
 }
 
 // This system updates the settings when a new value for a setting is selected, and marks
-// the button as the one currently selected
+// the button as the one currently selected, among the buttons of the same setting
 fn setting_button<T: Resource + Component + PartialEq + Copy>(
     interaction_query: Query<(&Interaction, &T, Entity), (Changed<Interaction>, With<Button>)>,
-    mut selected_query: Query<(Entity, &mut UiImage), With<SelectedOption>>,
+    mut selected_query: Query<(Entity, &mut UiImage), (With<SelectedOption>, With<T>)>,
     mut commands: Commands,
     mut setting: ResMut<T>,
 ) {
     for (interaction, button_setting, entity) in &interaction_query {
         if *interaction == Interaction::Pressed && *setting != *button_setting {
-            let (previous_button, mut previous_image) = selected_query.single_mut();
-            previous_image.color = NORMAL_BUTTON;
-            commands.entity(previous_button).remove::<SelectedOption>();
+            // None selected when the saved value isn't offered anymore
+            if let Ok((previous_button, mut previous_image)) = selected_query.get_single_mut() {
+                previous_image.color = NORMAL_BUTTON;
+                commands.entity(previous_button).remove::<SelectedOption>();
+            }
             commands.entity(entity).insert(SelectedOption);
             *setting = *button_setting;
         }
@@ -142,7 +180,13 @@ fn menu_setup(mut menu_state: ResMut<NextState<MenuState>>) {
     menu_state.set(MenuState::Main);
 }
 
-fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn main_menu_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    pkv: Res<PkvStore>,
+    language: Res<Language>,
+    load_error: Res<LoadError>,
+) {
     // Common style for all buttons on the screen
     let button_style = Style {
         width: Val::Px(250.0),
@@ -208,10 +252,46 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         }),
                     );
 
-                    // Display three buttons for each action available from the main menu:
+                    // Why the last game couldn't start
+                    if let Some(error) = &load_error.0 {
+                        parent.spawn(
+                            TextBundle::from_section(
+                                error.clone(),
+                                TextStyle {
+                                    font_size: 20.0,
+                                    color: TEXT_COLOR,
+                                    ..default()
+                                },
+                            )
+                            .with_style(Style {
+                                max_width: Val::Px(600.0),
+                                ..default()
+                            }),
+                        );
+                    }
+
+                    // Display a button for each action available from the main menu:
+                    // - continue, only with a save from this version
                     // - new game
                     // - settings
                     // - quit
+                    if load_game(&pkv).is_some() {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: NORMAL_BUTTON.into(),
+                                    ..default()
+                                },
+                                MenuButtonAction::Continue,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    language.translate("Continue"),
+                                    button_text_style.clone(),
+                                ));
+                            });
+                    }
                     parent
                         .spawn((
                             ButtonBundle {
@@ -223,7 +303,7 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         ))
                         .with_children(|parent| {
                             parent.spawn(TextBundle::from_section(
-                                "New Game",
+                                language.translate("New Game"),
                                 button_text_style.clone(),
                             ));
                         });
@@ -238,7 +318,7 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         ))
                         .with_children(|parent| {
                             parent.spawn(TextBundle::from_section(
-                                "Settings",
+                                language.translate("Settings"),
                                 button_text_style.clone(),
                             ));
                         });
@@ -252,13 +332,16 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                             MenuButtonAction::Quit,
                         ))
                         .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section("Quit", button_text_style));
+                            parent.spawn(TextBundle::from_section(
+                                language.translate("Quit"),
+                                button_text_style,
+                            ));
                         });
                 });
         });
 }
 
-fn settings_menu_setup(mut commands: Commands) {
+fn settings_menu_setup(mut commands: Commands, language: Res<Language>) {
     let button_style = Style {
         width: Val::Px(200.0),
         height: Val::Px(65.0),
@@ -303,6 +386,7 @@ fn settings_menu_setup(mut commands: Commands) {
                     for (action, text) in [
                         (MenuButtonAction::SettingsDisplay, "Display"),
                         (MenuButtonAction::SettingsSound, "Sound"),
+                        (MenuButtonAction::SettingsGame, "Game"),
                         (MenuButtonAction::BackToMainMenu, "Back"),
                     ] {
                         parent
@@ -316,7 +400,7 @@ fn settings_menu_setup(mut commands: Commands) {
                             ))
                             .with_children(|parent| {
                                 parent.spawn(TextBundle::from_section(
-                                    text,
+                                    language.translate(text),
                                     button_text_style.clone(),
                                 ));
                             });
@@ -325,7 +409,11 @@ fn settings_menu_setup(mut commands: Commands) {
         });
 }
 
-fn display_settings_menu_setup(mut commands: Commands, display_quality: Res<DisplayQuality>) {
+fn display_settings_menu_setup(
+    mut commands: Commands,
+    display_quality: Res<DisplayQuality>,
+    language: Res<Language>,
+) {
     let button_style = Style {
         width: Val::Px(200.0),
         height: Val::Px(65.0),
@@ -380,7 +468,7 @@ fn display_settings_menu_setup(mut commands: Commands, display_quality: Res<Disp
                         .with_children(|parent| {
                             // Display a label for the current setting
                             parent.spawn(TextBundle::from_section(
-                                "Display Quality",
+                                language.translate("Display Quality"),
                                 button_text_style.clone(),
                             ));
                             // Display a button for each possible value
@@ -403,7 +491,11 @@ fn display_settings_menu_setup(mut commands: Commands, display_quality: Res<Disp
                                 ));
                                 entity.with_children(|parent| {
                                     parent.spawn(TextBundle::from_section(
-                                        format!("{quality_setting:?}"),
+                                        language.translate(match quality_setting {
+                                            DisplayQuality::Low => "Low",
+                                            DisplayQuality::Medium => "Medium",
+                                            DisplayQuality::High => "High",
+                                        }),
                                         button_text_style.clone(),
                                     ));
                                 });
@@ -423,13 +515,22 @@ fn display_settings_menu_setup(mut commands: Commands, display_quality: Res<Disp
                             MenuButtonAction::BackToSettings,
                         ))
                         .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section("Back", button_text_style));
+                            parent.spawn(TextBundle::from_section(
+                                language.translate("Back"),
+                                button_text_style,
+                            ));
                         });
                 });
         });
 }
 
-fn sound_settings_menu_setup(mut commands: Commands, volume: Res<Volume>) {
+fn sound_settings_menu_setup(
+    mut commands: Commands,
+    volume: Res<Volume>,
+    music_volume: Res<MusicVolume>,
+    sfx_volume: Res<SfxVolume>,
+    language: Res<Language>,
+) {
     let button_style = Style {
         width: Val::Px(200.0),
         height: Val::Px(65.0),
@@ -481,7 +582,7 @@ fn sound_settings_menu_setup(mut commands: Commands, volume: Res<Volume>) {
                         })
                         .with_children(|parent| {
                             parent.spawn(TextBundle::from_section(
-                                "Volume",
+                                language.translate("Volume"),
                                 button_text_style.clone(),
                             ));
                             for volume_setting in [0, 1, 2, 3, 4, 5, 6, 7, 8, 9] {
@@ -502,6 +603,163 @@ fn sound_settings_menu_setup(mut commands: Commands, volume: Res<Volume>) {
                                 }
                             }
                         });
+                    // Buses on top of the master volume
+                    let bus_text_style = TextStyle {
+                        font_size: 24.0,
+                        ..button_text_style.clone()
+                    };
+                    spawn_setting_row(
+                        parent,
+                        language.translate("Music"),
+                        &[0, 3, 6, 9].map(|level| (MusicVolume(level), level.to_string())),
+                        *music_volume,
+                        &bus_text_style,
+                    );
+                    spawn_setting_row(
+                        parent,
+                        language.translate("Effects"),
+                        &[0, 3, 6, 9].map(|level| (SfxVolume(level), level.to_string())),
+                        *sfx_volume,
+                        &bus_text_style,
+                    );
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: button_style,
+                                background_color: NORMAL_BUTTON.into(),
+                                ..default()
+                            },
+                            MenuButtonAction::BackToSettings,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                language.translate("Back"),
+                                button_text_style,
+                            ));
+                        });
+                });
+        });
+}
+
+// A label then a button for each possible value, `setting_button::<T>` handles the clicks.
+fn spawn_setting_row<T: Component + PartialEq + Copy>(
+    parent: &mut ChildBuilder,
+    label: &str,
+    options: &[(T, String)],
+    current: T,
+    text_style: &TextStyle,
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: CRIMSON.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, text_style.clone()));
+            for (option, text) in options {
+                let mut entity = parent.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            height: Val::Px(48.0),
+                            padding: UiRect::horizontal(Val::Px(10.0)),
+                            margin: UiRect::all(Val::Px(10.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: NORMAL_BUTTON.into(),
+                        ..default()
+                    },
+                    *option,
+                ));
+                entity.with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(text.clone(), text_style.clone()));
+                });
+                if current == *option {
+                    entity.insert(SelectedOption);
+                }
+            }
+        });
+}
+
+fn game_settings_menu_setup(
+    mut commands: Commands,
+    language: Res<Language>,
+    game_speed: Res<GameSpeed>,
+    key_bindings: Res<KeyBindings>,
+) {
+    let button_style = Style {
+        width: Val::Px(200.0),
+        height: Val::Px(65.0),
+        margin: UiRect::all(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 40.0,
+        color: TEXT_COLOR,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            OnGameSettingsMenuScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: CRIMSON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_setting_row(
+                        parent,
+                        language.translate("Language"),
+                        &[
+                            (Language::English, "English".to_string()),
+                            (Language::Spanish, "Espanol".to_string()),
+                        ],
+                        *language,
+                        &button_text_style,
+                    );
+                    spawn_setting_row(
+                        parent,
+                        language.translate("Speed"),
+                        &[50, 100, 150, 200].map(|speed| (GameSpeed(speed), format!("{speed}%"))),
+                        *game_speed,
+                        &button_text_style,
+                    );
+                    spawn_setting_row(
+                        parent,
+                        language.translate("Keys"),
+                        &[
+                            (KeyBindings::Qwerty, "WASD".to_string()),
+                            (KeyBindings::Azerty, "ZQSD".to_string()),
+                            (KeyBindings::Arrows, "Arrows".to_string()),
+                        ],
+                        *key_bindings,
+                        &button_text_style,
+                    );
                     parent
                         .spawn((
                             ButtonBundle {
@@ -512,7 +770,10 @@ fn sound_settings_menu_setup(mut commands: Commands, volume: Res<Volume>) {
                             MenuButtonAction::BackToSettings,
                         ))
                         .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section("Back", button_text_style));
+                            parent.spawn(TextBundle::from_section(
+                                language.translate("Back"),
+                                button_text_style,
+                            ));
                         });
                 });
         });
@@ -526,6 +787,11 @@ fn menu_action(
     mut app_exit_events: EventWriter<AppExit>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut game_state: ResMut<NextState<GameState>>,
+    pkv: Res<PkvStore>,
+    mut run_seed: ResMut<RunSeed>,
+    mut stage_seed: ResMut<StageSeed>,
+    mut stage_id: ResMut<StageId>,
+    mut pending_save: ResMut<PendingSave>,
 ) {
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Pressed {
@@ -533,8 +799,23 @@ fn menu_action(
                 MenuButtonAction::Quit => {
                     app_exit_events.send(AppExit::Success);
                 }
+                MenuButtonAction::Continue => {
+                    // Same seed, same map, then `apply_save` put everyone back
+                    if let Some(save_game) = load_game(&pkv) {
+                        *run_seed = RunSeed(save_game.seed);
+                        if let Some(saved_stage_seed) = StageSeed::new(&save_game.public_key) {
+                            *stage_seed = saved_stage_seed;
+                        }
+                        *stage_id = StageId(save_game.stage_id.clone());
+                        pending_save.0 = Some(save_game);
+                        game_state.set(GameState::Loading);
+                        menu_state.set(MenuState::Disabled);
+                    }
+                }
                 MenuButtonAction::Play => {
-                    game_state.set(GameState::Game);
+                    pending_save.0 = None;
+                    *stage_id = StageId::default();
+                    game_state.set(GameState::Loading);
                     menu_state.set(MenuState::Disabled);
                 }
                 MenuButtonAction::Settings => menu_state.set(MenuState::Settings),
@@ -544,6 +825,9 @@ fn menu_action(
                 MenuButtonAction::SettingsSound => {
                     menu_state.set(MenuState::SettingsSound);
                 }
+                MenuButtonAction::SettingsGame => {
+                    menu_state.set(MenuState::SettingsGame);
+                }
                 MenuButtonAction::BackToMainMenu => menu_state.set(MenuState::Main),
                 MenuButtonAction::BackToSettings => {
                     menu_state.set(MenuState::Settings);
@@ -4,11 +4,13 @@
 
 pub mod game;
 pub mod menu;
+pub mod settings;
 pub mod splash;
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+pub const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
 
 // // Enum that will be used as a global state for the game
 // #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
@@ -19,18 +21,183 @@ const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
 //     Game,
 // }
 
-// One of the two settings that can be set through the menu. It will be a resource in the app
-#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
+// One of the settings that can be set through the menu. It will be a resource in the app
+#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum DisplayQuality {
     Low,
     Medium,
     High,
 }
 
-// One of the two settings that can be set through the menu. It will be a resource in the app
-#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
+impl DisplayQuality {
+    // Window scale factor, `None` is the one from the OS.
+    pub fn resolution_scale(&self) -> Option<f32> {
+        match self {
+            DisplayQuality::Low => Some(1.),
+            DisplayQuality::Medium | DisplayQuality::High => None,
+        }
+    }
+
+    pub fn msaa(&self) -> Msaa {
+        match self {
+            DisplayQuality::Low | DisplayQuality::Medium => Msaa::Off,
+            DisplayQuality::High => Msaa::Sample4,
+        }
+    }
+
+    // Damage numbers, hurt tint.
+    pub fn has_effects(&self) -> bool {
+        *self != DisplayQuality::Low
+    }
+}
+
+// One of the settings that can be set through the menu. It will be a resource in the app
+#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub struct Volume(pub u32);
 
+// Music bus, 0 to 9, on top of the master `Volume`.
+#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct MusicVolume(pub u32);
+
+// Sound effects bus, 0 to 9, on top of the master `Volume`.
+#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct SfxVolume(pub u32);
+
+// Bus a sound plays on, to spawn along its `AudioBundle`, no sounds yet.
+#[allow(unused)]
+#[derive(Component, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AudioBus {
+    Music,
+    Sfx,
+}
+
+// One of the settings that can be set through the menu. It will be a resource in the app
+#[derive(
+    Resource, Debug, Component, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize,
+)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Language {
+    // Menu, result and HUD texts, English is the key.
+    pub fn translate(&self, text: &'static str) -> &'static str {
+        match self {
+            Language::English => text,
+            Language::Spanish => match text {
+                "Continue" => "Continuar",
+                "New Game" => "Nueva partida",
+                "Settings" => "Ajustes",
+                "Quit" => "Salir",
+                "Display" => "Pantalla",
+                "Sound" => "Sonido",
+                "Game" => "Juego",
+                "Back" => "Volver",
+                "Display Quality" => "Calidad",
+                "Low" => "Baja",
+                "Medium" => "Media",
+                "High" => "Alta",
+                "Volume" => "Volumen",
+                "Language" => "Idioma",
+                "Speed" => "Velocidad",
+                "Keys" => "Teclas",
+                "Music" => "Musica",
+                "Effects" => "Efectos",
+                // Result screen
+                "GAME OVER!" => "FIN DEL JUEGO!",
+                "STAGE CLEAR!" => "NIVEL SUPERADO!",
+                "Retry" => "Reintentar",
+                "Replay" => "Repetir",
+                "Next Stage" => "Siguiente",
+                "Menu" => "Menu",
+                "Time" => "Tiempo",
+                "Damage dealt" => "Dano causado",
+                "Damage taken" => "Dano recibido",
+                "Chests opened" => "Cofres abiertos",
+                // Objectives
+                "Reach exit" => "Llegar a la salida",
+                "Open chests" => "Abrir cofres",
+                "Kill monsters" => "Matar monstruos",
+                "Survive" => "Sobrevivir",
+                "Protect" => "Proteger",
+                _ => text,
+            },
+        }
+    }
+}
+
+// One of the settings that can be set through the menu, in percent of the normal speed.
+#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct GameSpeed(pub u32);
+
+impl Default for GameSpeed {
+    fn default() -> Self {
+        Self(100)
+    }
+}
+
+// One of the settings that can be set through the menu, the layout used to walk, attack and open.
+#[derive(
+    Resource, Debug, Component, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize,
+)]
+pub enum KeyBindings {
+    // WASD, arrows too
+    #[default]
+    Qwerty,
+    // ZQSD, arrows too
+    Azerty,
+    // Arrows, X to attack, C to open
+    Arrows,
+}
+
+impl KeyBindings {
+    pub fn up(&self) -> &'static [KeyCode] {
+        match self {
+            KeyBindings::Qwerty => &[KeyCode::KeyW, KeyCode::ArrowUp],
+            KeyBindings::Azerty => &[KeyCode::KeyZ, KeyCode::ArrowUp],
+            KeyBindings::Arrows => &[KeyCode::ArrowUp],
+        }
+    }
+
+    pub fn down(&self) -> &'static [KeyCode] {
+        match self {
+            KeyBindings::Qwerty | KeyBindings::Azerty => &[KeyCode::KeyS, KeyCode::ArrowDown],
+            KeyBindings::Arrows => &[KeyCode::ArrowDown],
+        }
+    }
+
+    pub fn left(&self) -> &'static [KeyCode] {
+        match self {
+            KeyBindings::Qwerty => &[KeyCode::KeyA, KeyCode::ArrowLeft],
+            KeyBindings::Azerty => &[KeyCode::KeyQ, KeyCode::ArrowLeft],
+            KeyBindings::Arrows => &[KeyCode::ArrowLeft],
+        }
+    }
+
+    pub fn right(&self) -> &'static [KeyCode] {
+        match self {
+            KeyBindings::Qwerty | KeyBindings::Azerty => &[KeyCode::KeyD, KeyCode::ArrowRight],
+            KeyBindings::Arrows => &[KeyCode::ArrowRight],
+        }
+    }
+
+    pub fn attack(&self) -> KeyCode {
+        match self {
+            KeyBindings::Qwerty | KeyBindings::Azerty => KeyCode::Space,
+            KeyBindings::Arrows => KeyCode::KeyX,
+        }
+    }
+
+    pub fn open(&self) -> KeyCode {
+        match self {
+            KeyBindings::Qwerty | KeyBindings::Azerty => KeyCode::KeyE,
+            KeyBindings::Arrows => KeyCode::KeyC,
+        }
+    }
+}
+
 // Generic system that takes a component as a parameter, and will despawn all entities with that component
 pub fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
     for entity in &to_despawn {
@@ -0,0 +1,138 @@
+use bevy::{audio::AudioSinkPlayback, prelude::*, window::PrimaryWindow};
+use bevy_pkv::PkvStore;
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{
+    AudioBus, DisplayQuality, GameSpeed, KeyBindings, Language, MusicVolume, SfxVolume, Volume,
+};
+
+// A menu setting kept in `PkvStore` under `KEY`.
+pub trait Setting: Resource + Serialize + DeserializeOwned + Copy {
+    const KEY: &'static str;
+}
+
+impl Setting for DisplayQuality {
+    const KEY: &'static str = "display_quality";
+}
+
+impl Setting for Volume {
+    const KEY: &'static str = "volume";
+}
+
+impl Setting for MusicVolume {
+    const KEY: &'static str = "music_volume";
+}
+
+impl Setting for SfxVolume {
+    const KEY: &'static str = "sfx_volume";
+}
+
+impl Setting for Language {
+    const KEY: &'static str = "language";
+}
+
+impl Setting for GameSpeed {
+    const KEY: &'static str = "game_speed";
+}
+
+impl Setting for KeyBindings {
+    const KEY: &'static str = "key_bindings";
+}
+
+// Load the settings saved last time, save and apply them whenever they change.
+pub fn settings_plugin(app: &mut App) {
+    app.add_systems(
+        Startup,
+        (
+            load_setting::<DisplayQuality>,
+            load_setting::<Volume>,
+            load_setting::<MusicVolume>,
+            load_setting::<SfxVolume>,
+            load_setting::<Language>,
+            load_setting::<GameSpeed>,
+            load_setting::<KeyBindings>,
+        ),
+    )
+    .add_systems(
+        Update,
+        (
+            save_setting::<DisplayQuality>.run_if(resource_changed::<DisplayQuality>),
+            save_setting::<Volume>.run_if(resource_changed::<Volume>),
+            save_setting::<MusicVolume>.run_if(resource_changed::<MusicVolume>),
+            save_setting::<SfxVolume>.run_if(resource_changed::<SfxVolume>),
+            save_setting::<Language>.run_if(resource_changed::<Language>),
+            save_setting::<GameSpeed>.run_if(resource_changed::<GameSpeed>),
+            save_setting::<KeyBindings>.run_if(resource_changed::<KeyBindings>),
+            apply_display_quality.run_if(resource_changed::<DisplayQuality>),
+            apply_volume,
+            apply_game_speed.run_if(resource_changed::<GameSpeed>),
+        ),
+    );
+}
+
+fn load_setting<T: Setting>(mut commands: Commands, pkv: Res<PkvStore>) {
+    // Missing or from an older shape, keep the default.
+    if let Ok(setting) = pkv.get::<T>(T::KEY) {
+        commands.insert_resource(setting);
+    }
+}
+
+fn save_setting<T: Setting>(mut pkv: ResMut<PkvStore>, setting: Res<T>) {
+    if let Err(err) = pkv.set(T::KEY, &*setting) {
+        println!("⚙️ Can't save {}: {}", T::KEY, err);
+    }
+}
+
+fn apply_display_quality(
+    display_quality: Res<DisplayQuality>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut msaa: ResMut<Msaa>,
+) {
+    println!("⚙️ Display quality:{:?}", *display_quality);
+    for mut window in windows.iter_mut() {
+        window
+            .resolution
+            .set_scale_factor_override(display_quality.resolution_scale());
+    }
+    *msaa = display_quality.msaa();
+}
+
+// Master times the bus, 0 to 1.
+pub fn get_bus_volume(volume: Volume, bus_volume: Option<u32>) -> f32 {
+    volume.0 as f32 / 9. * bus_volume.map_or(1., |bus_volume| bus_volume as f32 / 9.)
+}
+
+// On the sounds already playing when a volume change, and on new ones as they start.
+fn apply_volume(
+    volume: Res<Volume>,
+    music_volume: Res<MusicVolume>,
+    sfx_volume: Res<SfxVolume>,
+    mut global_volume: ResMut<GlobalVolume>,
+    sinks: Query<(Ref<AudioSink>, Option<&AudioBus>)>,
+) {
+    let is_changed = volume.is_changed() || music_volume.is_changed() || sfx_volume.is_changed();
+    if is_changed {
+        *global_volume = GlobalVolume::new(get_bus_volume(*volume, None));
+    }
+
+    for (sink, bus) in sinks.iter() {
+        if !is_changed && !sink.is_added() {
+            continue;
+        }
+        let bus_volume = bus.map(|bus| match bus {
+            AudioBus::Music => music_volume.0,
+            AudioBus::Sfx => sfx_volume.0,
+        });
+        sink.set_volume(get_bus_volume(*volume, bus_volume));
+    }
+}
+
+// Virtual time drive the fixed tick, so the whole run speed up or slow down.
+fn apply_game_speed(game_speed: Res<GameSpeed>, mut time: ResMut<Time<Virtual>>) {
+    time.set_relative_speed(game_speed.0 as f32 / 100.);
+}
+
+// Run condition for the optional feedback, e.g. damage numbers.
+pub fn has_effects(display_quality: Res<DisplayQuality>) -> bool {
+    display_quality.has_effects()
+}
@@ -2,16 +2,21 @@ use bevy::{
     prelude::*,
     sprite::{MaterialMesh2dBundle, Mesh2dHandle},
 };
-use bevy_stat_bars::Statbar;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 use crate::{
-    brains::fight::Fighter,
+    afterlife::stats::RunStats,
     characters::{
         actions::{Act, Action},
         bar::Health,
+        effect::{EffectEvent, StatusEffect, StatusEffects},
         entities::CharacterKind,
     },
-    core::{layer::SpriteLayer, state::GameState},
+    core::{
+        faction::FactionTable, layer::SpriteLayer, map::get_map_from_position, position::Position,
+        scene::ChunkMap, seed::RunSeed,
+    },
+    entry::game::OnGameScreen,
 };
 use std::fmt::Debug;
 
@@ -23,21 +28,40 @@ pub struct Damages(pub Vec<Damage>);
 #[derive(Clone, Default, Debug)]
 pub struct Damage {
     pub by: CharacterKind,
+    pub attacker: Option<Entity>,
     pub position: Vec2,
     pub power: f32,
     pub radius: f32,
     pub direction: Vec2,
     pub duration: f32,
+    // Status effects given on hit, e.g. poison
+    pub effects: Vec<StatusEffect>,
 }
 
 #[derive(Component)]
 pub struct DamageIndicator {
     pub duration: f32,
+    pub total: f32,
 }
 
 #[derive(Event)]
 pub struct DamageEvent(pub Damage);
 
+// What a damage did to a target, for feedback.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct HitEvent {
+    pub target: Entity,
+    pub position: Vec2,
+    pub amount: f32,
+    pub is_critical: bool,
+}
+
+const HIT_AREA_COLOR: Color = Color::srgba(1.0, 0.0, 0.0, 0.4);
+const DAMAGE_NUMBER_COLOR: Color = Color::WHITE;
+const CRITICAL_NUMBER_COLOR: Color = Color::srgb(1.0, 0.84, 0.0);
+const HURT_TINT_COLOR: Color = Color::srgb(1.0, 0.3, 0.3);
+const CRITICAL_TINT_COLOR: Color = Color::srgb(1.0, 0.6, 0.0);
+
 pub fn spawn_damage_indicator(
     mut commands: Commands,
     mut damage_events: EventReader<DamageEvent>,
@@ -45,21 +69,23 @@ pub fn spawn_damage_indicator(
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
     for DamageEvent(damage) in damage_events.read() {
-        // let shape = meshes.add(Circle {
-        //     radius: damage.radius,
-        // });
+        // Flash the hit area
+        let shape = meshes.add(Circle {
+            radius: damage.radius,
+        });
         commands.spawn((
-            // MaterialMesh2dBundle {
-            //     mesh: Mesh2dHandle(shape),
-            //     material: materials.add(Color::srgba(1.0, 0.0, 0.0, damage.power / 50.)),
-            //     transform: Transform::from_xyz(damage.position.x, damage.position.y, 0.0)
-            //         .with_scale(Vec3::new(damage.radius / 100.0, damage.radius / 100.0, 1.0)),
-            //     ..default()
-            // },
+            MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(shape),
+                material: materials.add(HIT_AREA_COLOR),
+                transform: Transform::from_xyz(damage.position.x, damage.position.y, 0.0),
+                ..default()
+            },
             SpriteLayer::Foreground,
             DamageIndicator {
                 duration: damage.duration,
+                total: damage.duration,
             },
+            OnGameScreen,
         ));
     }
 }
@@ -67,12 +93,133 @@ pub fn spawn_damage_indicator(
 pub fn despawn_damage_indicator(
     mut commands: Commands,
     time: Res<Time>,
-    mut query: Query<(Entity, &mut DamageIndicator)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut query: Query<(Entity, &mut DamageIndicator, &Handle<ColorMaterial>)>,
 ) {
-    for (entity, mut indicator) in query.iter_mut() {
+    for (entity, mut indicator, material) in query.iter_mut() {
         indicator.duration -= time.delta_seconds();
         if indicator.duration <= 0.0 {
+            materials.remove(material);
             commands.entity(entity).despawn();
+        } else if let Some(material) = materials.get_mut(material) {
+            // Fade out
+            let alpha = HIT_AREA_COLOR.alpha() * indicator.duration / indicator.total;
+            material.color = HIT_AREA_COLOR.with_alpha(alpha);
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct DamageNumber {
+    pub duration: f32,
+    pub total: f32,
+    pub color: Color,
+}
+
+const DAMAGE_NUMBER_DURATION: f32 = 0.8;
+const DAMAGE_NUMBER_RISE: f32 = 32.;
+
+pub fn spawn_damage_number(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut hit_events: EventReader<HitEvent>,
+) {
+    for hit in hit_events.read() {
+        let (color, font_size, text) = match hit.is_critical {
+            true => (CRITICAL_NUMBER_COLOR, 24.0, format!("{:.0}!", hit.amount)),
+            false => (DAMAGE_NUMBER_COLOR, 16.0, format!("{:.0}", hit.amount)),
+        };
+
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    text,
+                    TextStyle {
+                        font: asset_server.load("PixelOperator-Bold.ttf"),
+                        font_size,
+                        color,
+                    },
+                ),
+                // Above head
+                transform: Transform::from_xyz(hit.position.x, hit.position.y + 32., 0.0),
+                ..default()
+            },
+            SpriteLayer::Foreground,
+            DamageNumber {
+                duration: DAMAGE_NUMBER_DURATION,
+                total: DAMAGE_NUMBER_DURATION,
+                color,
+            },
+            OnGameScreen,
+        ));
+    }
+}
+
+pub fn update_damage_number(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut DamageNumber, &mut Transform, &mut Text)>,
+) {
+    for (entity, mut number, mut transform, mut text) in query.iter_mut() {
+        number.duration -= time.delta_seconds();
+        if number.duration <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        // Rise and fade
+        transform.translation.y += DAMAGE_NUMBER_RISE * time.delta_seconds() / number.total;
+        let alpha = number.duration / number.total;
+        for section in text.sections.iter_mut() {
+            section.style.color = number.color.with_alpha(alpha);
+        }
+    }
+}
+
+// Color to restore once the tint is over, e.g. a sealed grave.
+#[derive(Component)]
+pub struct HurtTint {
+    pub duration: f32,
+    pub previous: Color,
+}
+
+const HURT_TINT_DURATION: f32 = 0.2;
+
+pub fn spawn_hurt_tint(
+    mut commands: Commands,
+    mut hit_events: EventReader<HitEvent>,
+    mut sprites: Query<(&mut Sprite, Option<&HurtTint>)>,
+) {
+    for hit in hit_events.read() {
+        if let Ok((mut sprite, tint)) = sprites.get_mut(hit.target) {
+            // Already tinted, keep the color from before the first hit
+            let previous = tint.map_or(sprite.color, |tint| tint.previous);
+            let alpha = sprite.color.alpha();
+            sprite.color = match hit.is_critical {
+                true => CRITICAL_TINT_COLOR,
+                false => HURT_TINT_COLOR,
+            }
+            .with_alpha(alpha);
+            commands.entity(hit.target).insert(HurtTint {
+                duration: HURT_TINT_DURATION,
+                previous,
+            });
+        }
+    }
+}
+
+pub fn update_hurt_tint(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut HurtTint, &mut Sprite)>,
+) {
+    for (entity, mut tint, mut sprite) in query.iter_mut() {
+        tint.duration -= time.delta_seconds();
+        if tint.duration <= 0.0 {
+            // Keep the corpse fade
+            let alpha = sprite.color.alpha();
+            sprite.color = tint.previous.with_alpha(alpha);
+            commands.entity(entity).remove::<HurtTint>();
         }
     }
 }
@@ -80,66 +227,319 @@ pub fn despawn_damage_indicator(
 #[derive(Component)]
 pub struct Death;
 
+// Reduce incoming damage, from `defend` in stage.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Defense(pub f32);
+
+// Seeded from the run so the same run crit the same.
+#[derive(Resource)]
+pub struct DamageRng(pub StdRng);
+
+impl Default for DamageRng {
+    fn default() -> Self {
+        Self(StdRng::seed_from_u64(0))
+    }
+}
+
+impl From<&RunSeed> for DamageRng {
+    fn from(run_seed: &RunSeed) -> Self {
+        Self(StdRng::seed_from_u64(run_seed.0))
+    }
+}
+
+pub fn reset_damage_rng(mut commands: Commands, run_seed: Res<RunSeed>) {
+    commands.insert_resource(DamageRng::from(&*run_seed));
+}
+
+pub const CRITICAL_CHANCE: f32 = 0.1;
+pub const CRITICAL_MULTIPLIER: f32 = 2.;
+pub const KNOCKBACK_DISTANCE: f32 = 16.;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub amount: f32,
+    pub is_critical: bool,
+    pub knockback: Vec2,
+}
+
+// What a damage do to a target at `target_xy`, `roll` in 0..1 decide the crit.
+pub fn compute_hit(damage: &Damage, target_xy: Vec2, defend: f32, roll: f32) -> Option<Hit> {
+    // Only inside the radius
+    let distance = damage.position.distance(target_xy);
+    if distance > damage.radius {
+        return None;
+    }
+
+    // Full at center, none at edge
+    let falloff = if damage.radius > 0. {
+        1. - distance / damage.radius
+    } else {
+        1.
+    };
+
+    // 100 defend = half damage
+    let reduction = 100. / (100. + defend.max(0.));
+
+    let is_critical = roll < CRITICAL_CHANCE;
+    let multiplier = if is_critical { CRITICAL_MULTIPLIER } else { 1. };
+
+    Some(Hit {
+        amount: damage.power * falloff * reduction * multiplier,
+        is_critical,
+        knockback: damage.direction.normalize_or_zero() * KNOCKBACK_DISTANCE * falloff,
+    })
+}
+
+// Where the target end up after a knockback, stay put if land on unwalkable.
+pub fn apply_knockback(walkables: &[Vec<bool>], from: Vec2, knockback: Vec2) -> Vec2 {
+    let to = from + knockback;
+    let (x, y) = get_map_from_position(to, None);
+    let is_walkable = walkables
+        .get(y)
+        .and_then(|row| row.get(x))
+        .copied()
+        .unwrap_or(false);
+
+    if is_walkable {
+        to
+    } else {
+        from
+    }
+}
+
+#[allow(clippy::type_complexity)]
 pub fn update_damage(
     mut commands: Commands,
-    mut targets: Query<(Entity, &CharacterKind, &mut Health, &mut Action)>,
+    mut targets: Query<(
+        Entity,
+        &CharacterKind,
+        &mut Health,
+        &mut Action,
+        &mut Position,
+        Option<&Defense>,
+        Option<&StatusEffects>,
+    )>,
     mut damage_events: EventReader<DamageEvent>,
-    mut game_state: ResMut<NextState<GameState>>,
+    mut hit_events: EventWriter<HitEvent>,
+    mut effect_events: EventWriter<EffectEvent>,
+    mut run_stats: ResMut<RunStats>,
+    mut damage_rng: ResMut<DamageRng>,
+    chunk_map: Res<ChunkMap>,
+    factions: Res<FactionTable>,
 ) {
     for DamageEvent(damage) in damage_events.read() {
-        targets
-            .iter_mut()
-            .for_each(|(entity, kind, mut hp, mut actor_action)| {
-                if actor_action.0 != Act::Die && *kind != damage.by {
-                    // TODO: some bounce from damage
-                    // let player_position = Vec2::new(
-                    //     player_transform.translation.x,
-                    //     player_transform.translation.y,
-                    // );
-                    // let direction_to_damage = (damage.position - player_position).normalize();
-                    // let move_direction = direction_to_damage * damage.power * damage.radius;
-                    // player_transform.translation += Vec3::new(move_direction.x, move_direction.y, 0.0);
-
-                    *hp -= damage.power;
-
-                    // Action
-                    if hp.value > 0. {
-                        *actor_action = Action(Act::Hurt);
-                    } else {
-                        *actor_action = Action(Act::Die);
-                        println!("💥 actor_action: {:?}", actor_action.0);
-
-                        commands.entity(entity).insert(Death);
-                        commands.entity(entity).remove::<Fighter>();
-                        commands.entity(entity).remove::<Statbar<Health>>();
-
-                        // Human?
-                        match kind {
-                            CharacterKind::Human => {
-                                println!("💥  GameState::Over");
-                                game_state.set(GameState::Over);
-                                game_state.set(GameState::Menu);
-                            }
-                            _ => {
-                                //
-                            }
-                        }
-                    }
-                }
+        for (entity, kind, mut hp, mut actor_action, mut position, defense, effects) in
+            targets.iter_mut()
+        {
+            // Allies and neutrals are spared, attacker too
+            if actor_action.0 == Act::Die
+                || damage.attacker == Some(entity)
+                || !factions.is_hostile(&damage.by, kind)
+            {
+                continue;
+            }
+
+            let defend = defense.map(|defense| defense.0).unwrap_or(0.);
+            let roll = damage_rng.0.gen::<f32>();
+            let Some(mut hit) = compute_hit(damage, position.xy, defend, roll) else {
+                continue;
+            };
+
+            // Shield
+            hit.amount *= effects.map_or(1., |effects| effects.incoming_multiplier());
+
+            if hit.is_critical {
+                println!("💥 Critical! {:.1}", hit.amount);
+            }
+
+            let hp_before = hp.value;
+            *hp -= hit.amount;
+
+            hit_events.send(HitEvent {
+                target: entity,
+                position: position.xy,
+                amount: hit.amount,
+                is_critical: hit.is_critical,
             });
+
+            for effect in damage.effects.iter() {
+                effect_events.send(EffectEvent {
+                    target: entity,
+                    effect: effect.clone(),
+                });
+            }
+
+            // Bounce
+            position.xy = apply_knockback(&chunk_map.walkables, position.xy, hit.knockback);
+
+            // Stats
+            let dealt = hp_before - hp.value;
+            if damage.by == CharacterKind::Human {
+                run_stats.damage_dealt += dealt;
+            }
+            if *kind == CharacterKind::Human {
+                run_stats.damage_taken += dealt;
+            }
+
+            // Action
+            if hp.value > 0. {
+                *actor_action = Action(Act::Hurt);
+            } else {
+                *actor_action = Action(Act::Die);
+                println!("💥 actor_action: {:?}", actor_action.0);
+
+                // See `handle_death` for the rest
+                commands.entity(entity).insert(Death);
+            }
+        }
     }
 }
 
-// pub fn despawn_fighter_on_death_system<T>(
-//     mut commands: Commands,
-//     mut characters: Query<(Entity, &Action),( With<T>, With<Fighter>, WithOut<Death>)>,
-// ) where
-//     T: CharacterInfo + 'static,
-// {
-//     for (entity, action) in characters.iter_mut() {
-//         if action.0 == Act::Die {
-//             commands.entity(entity).remove::<Fighter>();
-//             println!("💥 remove:Fighter");
-//         }
-//     }
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        map::{convert_map_to_screen, get_position_from_map},
+        stage::load_stage_from_yaml,
+    };
+
+    fn damage_at(position: Vec2) -> Damage {
+        Damage {
+            by: CharacterKind::Monster,
+            attacker: None,
+            position,
+            power: 10.,
+            radius: 40.,
+            direction: Vec2::X,
+            duration: 0.5,
+            effects: vec![],
+        }
+    }
+
+    // No crit
+    const ROLL: f32 = 0.99;
+
+    #[test]
+    fn test_hit_outside_radius() {
+        let damage = damage_at(Vec2::ZERO);
+        assert!(compute_hit(&damage, Vec2::new(41., 0.), 0., ROLL).is_none());
+    }
+
+    #[test]
+    fn test_hit_falloff() {
+        let damage = damage_at(Vec2::ZERO);
+        let center = compute_hit(&damage, Vec2::ZERO, 0., ROLL).unwrap();
+        let half = compute_hit(&damage, Vec2::new(20., 0.), 0., ROLL).unwrap();
+
+        assert_eq!(center.amount, 10.);
+        assert_eq!(half.amount, 5.);
+    }
+
+    #[test]
+    fn test_hit_defense() {
+        let damage = damage_at(Vec2::ZERO);
+        let hit = compute_hit(&damage, Vec2::ZERO, 100., ROLL).unwrap();
+
+        assert_eq!(hit.amount, 5.);
+    }
+
+    #[test]
+    fn test_hit_critical() {
+        let damage = damage_at(Vec2::ZERO);
+        let hit = compute_hit(&damage, Vec2::ZERO, 0., 0.).unwrap();
+
+        assert!(hit.is_critical);
+        assert_eq!(hit.amount, 10. * CRITICAL_MULTIPLIER);
+    }
+
+    #[test]
+    fn test_hit_same_seed_same_crits() {
+        let hits = |seed| {
+            let mut damage_rng = DamageRng::from(&RunSeed(seed));
+            let damage = damage_at(Vec2::ZERO);
+            (0..64)
+                .map(|_| compute_hit(&damage, Vec2::ZERO, 0., damage_rng.0.gen::<f32>()).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let crits = |hits: &[Hit]| hits.iter().filter(|hit| hit.is_critical).count();
+
+        assert_eq!(hits(42), hits(42));
+        assert!(crits(&hits(42)) > 0);
+        assert_ne!(hits(42), hits(7));
+    }
+
+    #[test]
+    fn test_knockback_direction() {
+        let damage = damage_at(Vec2::ZERO);
+        let hit = compute_hit(&damage, Vec2::ZERO, 0., ROLL).unwrap();
+
+        assert_eq!(hit.knockback, Vec2::X * KNOCKBACK_DISTANCE);
+    }
+
+    #[test]
+    fn test_knockback_respect_walkable() {
+        let from = get_position_from_map(1, 1, None).translation.xy();
+        let knockback = Vec2::X * 46.;
+
+        let walkables = vec![vec![true, true, true], vec![true, true, false]];
+        assert_eq!(apply_knockback(&walkables, from, knockback), from);
+
+        let walkables = vec![vec![true, true, true], vec![true, true, true]];
+        assert_eq!(
+            apply_knockback(&walkables, from, knockback),
+            from + knockback
+        );
+    }
+
+    #[test]
+    fn test_neutral_crab_is_spared() {
+        let stage = load_stage_from_yaml("assets/stage_1-1.yml").unwrap();
+        let crab = &stage.npcs[0];
+        let (x, y) = convert_map_to_screen(crab.position.clone()).unwrap();
+        let xy = get_position_from_map(x, y, None).translation.xy();
+
+        let mut app = App::new();
+        app.add_event::<DamageEvent>()
+            .add_event::<HitEvent>()
+            .add_event::<EffectEvent>()
+            .init_resource::<RunStats>()
+            .init_resource::<DamageRng>()
+            .init_resource::<ChunkMap>()
+            .insert_resource(stage.factions.clone())
+            .add_systems(Update, update_damage);
+
+        let full = crab.health as f32;
+        let crab = app
+            .world_mut()
+            .spawn((
+                crab.kind,
+                Health::new_full(full),
+                Action(Act::Idle),
+                Position { xy },
+            ))
+            .id();
+
+        // A skeleton beside it, so the hits do land
+        let skeleton = app
+            .world_mut()
+            .spawn((
+                CharacterKind::Monster,
+                Health::new_full(full),
+                Action(Act::Idle),
+                Position { xy },
+            ))
+            .id();
+
+        for by in [CharacterKind::Human, CharacterKind::Monster] {
+            app.world_mut().send_event(DamageEvent(Damage {
+                by,
+                ..damage_at(xy)
+            }));
+        }
+        app.update();
+
+        let hp = |app: &App, entity| app.world().get::<Health>(entity).unwrap().value;
+        assert_eq!(hp(&app, crab), full);
+        assert!(hp(&app, skeleton) < full);
+    }
+}
@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+use bevy_spritesheet_animation::prelude::*;
+use bevy_stat_bars::Statbar;
+use big_brain::prelude::*;
+
+use crate::{
+    animations::{build::AniRegistry, entities::AniType, utils::get_animation_name},
+    brains::{fight::Fighter, loot::Looter, order::Orders, patrol::Patrol},
+    characters::{
+        actions::Act,
+        bar::{Health, HealthValue, Morale, Stamina, StatRate},
+        effect::StatusEffects,
+        entities::{CharacterId, CharacterKind},
+    },
+    core::{position::Position, stage::CharacterDef},
+};
+
+use super::damage::Death;
+
+// Lie there a bit before fading away.
+const CORPSE_DELAY: f32 = 2.;
+const CORPSE_FADE: f32 = 1.;
+
+#[derive(Event, Debug, Clone)]
+pub struct DeathEvent {
+    pub entity: Entity,
+    pub character_id: CharacterId,
+    pub kind: CharacterKind,
+}
+
+#[derive(Component, Debug, Default)]
+pub struct Corpse {
+    pub elapsed: f32,
+}
+
+// What the party picked up from the fallen.
+#[derive(Resource, Default, Debug)]
+pub struct Inventory(pub Vec<String>);
+
+pub fn reset_inventory(mut inventory: ResMut<Inventory>) {
+    *inventory = Inventory::default();
+}
+
+// Turn the fallen into a corpse, without `Position` nobody target it anymore.
+#[allow(clippy::type_complexity)]
+pub fn handle_death(
+    mut commands: Commands,
+    mut deaths: Query<
+        (
+            Entity,
+            &AniType,
+            &CharacterDef,
+            Option<&mut SpritesheetAnimation>,
+        ),
+        Added<Death>,
+    >,
+    ani_registry: Res<AniRegistry>,
+    mut inventory: ResMut<Inventory>,
+    mut death_events: EventWriter<DeathEvent>,
+) {
+    for (entity, ani_type, character_def, animation) in deaths.iter_mut() {
+        println!("⚰️ Death:{:?}", character_def.character_id);
+
+        // Die once, `mode: once` in char.ani.json, when drawn
+        let animation_id = ani_registry.get(get_animation_name(ani_type, Act::Die));
+        if let (Some(mut animation), Some(animation_id)) = (animation, animation_id) {
+            animation.switch(animation_id);
+        }
+
+        // Drop loot
+        for drop in character_def.drops.iter() {
+            println!("🎁 Drop:{}", drop);
+            inventory.0.push(drop.clone());
+        }
+
+        // Removing the builder also despawn the thinker.
+        commands
+            .entity(entity)
+            .remove::<(ThinkerBuilder, Fighter, Looter, Orders, Patrol)>()
+            .remove::<(
+                Position,
+                Statbar<Health>,
+                StatusEffects,
+                StatRate<HealthValue>,
+                Statbar<Stamina>,
+                Statbar<Morale>,
+            )>()
+            .insert(Corpse::default());
+
+        death_events.send(DeathEvent {
+            entity,
+            character_id: character_def.character_id.clone(),
+            kind: character_def.kind,
+        });
+    }
+}
+
+pub fn update_corpse(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut corpses: Query<(Entity, &mut Corpse, &mut Sprite)>,
+) {
+    for (entity, mut corpse, mut sprite) in corpses.iter_mut() {
+        corpse.elapsed += time.delta_seconds();
+
+        // Fade out
+        let fade = (corpse.elapsed - CORPSE_DELAY) / CORPSE_FADE;
+        if fade > 0. {
+            sprite.color.set_alpha((1. - fade).max(0.));
+        }
+
+        if fade >= 1. {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
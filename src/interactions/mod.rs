@@ -1,2 +1,4 @@
 pub mod damage;
+pub mod death;
+pub mod pick;
 pub mod toggle;
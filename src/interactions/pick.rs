@@ -0,0 +1,157 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    brains::order::{Order, Orders},
+    characters::entities::CharacterKind,
+    core::{
+        faction::FactionTable,
+        layer::SpriteLayer,
+        map::{
+            get_cell_from_position, get_map_from_position, get_position_from_map, MapConfig,
+            MapPosition,
+        },
+        position::Position,
+        scene::ChunkMap,
+    },
+    entry::game::OnGameScreen,
+    interactions::toggle::Interactable,
+};
+
+// Character that receive the orders from picking.
+#[derive(Component, Debug)]
+pub struct Selected;
+
+#[derive(Component, Debug)]
+pub struct CellHighlight;
+
+const HIGHLIGHT_COLOR: Color = Color::srgba(1.0, 1.0, 0.4, 0.35);
+
+// Screen position of a click or a touch, if any this frame.
+fn get_picked_screen_position(
+    mouse_input: &ButtonInput<MouseButton>,
+    touches: &Touches,
+    window: &Window,
+) -> Option<Vec2> {
+    if mouse_input.just_pressed(MouseButton::Left) {
+        return window.cursor_position();
+    }
+
+    touches
+        .iter_just_pressed()
+        .next()
+        .map(|touch| touch.position())
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn pick_cell_system(
+    mut commands: Commands,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    touches: Res<Touches>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    chunk_map: Res<ChunkMap>,
+    factions: Res<FactionTable>,
+    mut heroes: Query<(Entity, &Position, &mut Orders, Option<&Selected>)>,
+    characters: Query<(Entity, &Position, &CharacterKind), Without<Orders>>,
+    interactables: Query<(Entity, &Position), (With<Interactable>, Without<CharacterKind>)>,
+    mut highlights: Query<&mut Transform, With<CellHighlight>>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Some(screen_position) = get_picked_screen_position(&mouse_input, &touches, window) else {
+        return;
+    };
+    let Some(world_position) = cameras
+        .iter()
+        .find_map(|(camera, transform)| camera.viewport_to_world_2d(transform, screen_position))
+    else {
+        return;
+    };
+
+    // Cell under the pointer
+    let Some((x, y)) = get_cell_from_position(world_position, &chunk_map.walkables) else {
+        return;
+    };
+    let is_walkable = chunk_map.walkables[y][x];
+    let is_at_cell = |position: &Position| get_map_from_position(position.xy, None) == (x, y);
+
+    println!("👆 Picked:{:?}", (x, y));
+
+    // Highlight
+    let cell_transform = get_position_from_map(x, y, None);
+    match highlights.get_single_mut() {
+        Ok(mut transform) => *transform = cell_transform,
+        Err(_) => {
+            let cell_size = MapConfig::default().cell_size as f32;
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: HIGHLIGHT_COLOR,
+                        custom_size: Some(Vec2::splat(cell_size)),
+                        ..default()
+                    },
+                    transform: cell_transform,
+                    ..default()
+                },
+                SpriteLayer::Foreground,
+                CellHighlight,
+                OnGameScreen,
+            ));
+        }
+    }
+
+    // Pick a hero to select it
+    if let Some(picked_hero) = heroes
+        .iter()
+        .find(|(_, position, _, _)| is_at_cell(position))
+        .map(|(entity, ..)| entity)
+    {
+        for (entity, _, _, selected) in heroes.iter() {
+            match (entity == picked_hero, selected.is_some()) {
+                (true, false) => {
+                    commands.entity(entity).insert(Selected);
+                }
+                (false, true) => {
+                    commands.entity(entity).remove::<Selected>();
+                }
+                _ => (),
+            }
+        }
+
+        println!("👆 Selected:{:?}", picked_hero);
+        return;
+    }
+
+    // What to do there
+    let order = if let Some((entity, ..)) = characters
+        .iter()
+        .find(|(_, p, kind)| factions.is_hostile(&CharacterKind::Human, kind) && is_at_cell(p))
+    {
+        Order::Attack(entity)
+    } else if let Some((entity, _)) = interactables.iter().find(|(_, p)| is_at_cell(p)) {
+        Order::Open(entity)
+    } else if is_walkable {
+        Order::GoTo(MapPosition { x, y })
+    } else {
+        return;
+    };
+
+    // Selected hero or the first one
+    let selected_orders = match heroes.iter().any(|(.., selected)| selected.is_some()) {
+        true => heroes
+            .iter_mut()
+            .find(|(.., selected)| selected.is_some())
+            .map(|(_, _, orders, _)| orders),
+        false => heroes.iter_mut().next().map(|(_, _, orders, _)| orders),
+    };
+
+    if let Some(mut orders) = selected_orders {
+        println!("📣 Order:{:?}", order);
+
+        // Hold shift to queue
+        let is_queued = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        orders.give(order, is_queued);
+    }
+}
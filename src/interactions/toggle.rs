@@ -2,38 +2,236 @@ use bevy::prelude::*;
 
 use crate::{
     characters::entities::CharacterKind,
-    core::chest::{ChestId, ChestState, Chests},
+    core::{
+        chest::{ChestId, ChestState, Chests},
+        gate::{GateId, GateState, Gates},
+        grave::Sealed,
+        lever::Switched,
+        objective::ObjectiveTracker,
+        scene::ChunkMap,
+    },
 };
 use std::fmt::Debug;
 
+// Max links to follow from one toggle, e.g. lever → gate, guard against cycles.
+const MAX_TOGGLE_HOPS: usize = 4;
+
 #[allow(unused)]
 #[derive(Resource, Default, Debug)]
 pub struct Toggles(pub Vec<Toggle>);
 
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interactable {
+    Chest,
+    Gate,
+    Lever,
+    PressurePlate,
+    Grave,
+}
+
+// Entity that receive the toggle forwarded from this one, e.g. a lever opening a gate.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InteractionTarget(pub Entity);
+
 #[allow(unused)]
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Debug)]
 pub struct Toggle {
     pub position: Vec2,
     pub by: CharacterKind,
-    // TODO: more generic with switch, door, ...
-    pub target: ChestId,
+    pub target: Entity,
 }
 
 #[derive(Event)]
 pub struct ToggleEvent(pub Toggle);
 
-// TODO: more generic with switch, door, ...
 #[allow(clippy::type_complexity)]
-pub fn update_toggle_chest(
+pub fn update_toggle(
+    mut commands: Commands,
     mut toggle_events: EventReader<ToggleEvent>,
+    interactables: Query<(
+        &Interactable,
+        Option<&ChestId>,
+        Option<&GateId>,
+        Option<&InteractionTarget>,
+    )>,
     mut chests: ResMut<Chests>,
+    mut gates: ResMut<Gates>,
+    chunk_map: Res<ChunkMap>,
+    objective_tracker: Res<ObjectiveTracker>,
 ) {
     for ToggleEvent(toggle) in toggle_events.read() {
-        if let Some(chest) = chests.0.get_mut(&toggle.target.0) {
-            if chest.status == ChestState::Close {
-                // Update the state
-                chest.status = ChestState::Open;
+        let mut target = Some(toggle.target);
+
+        for _ in 0..MAX_TOGGLE_HOPS {
+            let Some(entity) = target.take() else {
+                break;
+            };
+            let Ok((interactable, chest_id, gate_id, interaction_target)) =
+                interactables.get(entity)
+            else {
+                break;
+            };
+
+            match interactable {
+                Interactable::Chest => {
+                    if let Some(chest) = chest_id.and_then(|id| chests.0.get_mut(&id.0)) {
+                        if chest.status == ChestState::Close {
+                            // Update the state
+                            chest.status = ChestState::Open;
+                        }
+                    }
+                }
+                Interactable::Gate => {
+                    if let Some(gate) = gate_id.and_then(|id| gates.0.get_mut(&id.0)) {
+                        // Same rule as `open_exit_gate`, no way around the objectives
+                        let is_exit = chunk_map.exit_gate.as_ref() == Some(&gate.at);
+                        if is_exit && !objective_tracker.is_ready_to_exit() {
+                            println!("🚪 Exit locked until the objectives are done");
+                        } else if gate.status == GateState::Close {
+                            // Update the state
+                            gate.status = GateState::Open;
+                        }
+                    }
+                }
+                Interactable::Grave => {
+                    commands.entity(entity).insert(Sealed);
+                }
+                Interactable::Lever | Interactable::PressurePlate => {
+                    commands.entity(entity).insert(Switched);
+
+                    // Forward to linked entity
+                    target = interaction_target.map(|it| it.0);
+                }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        gate::Gate,
+        lever::{update_pressure_plate, PressurePlate},
+        map::{get_position_from_map, MapPosition},
+        objective::Objective,
+        position::Position,
+    };
+
+    const EXIT_AT: MapPosition = MapPosition { x: 3, y: 7 };
+
+    fn setup(objectives: &[Objective]) -> (App, Entity) {
+        let mut app = App::new();
+        app.add_event::<ToggleEvent>()
+            .init_resource::<Chests>()
+            .insert_resource(ChunkMap {
+                exit_gate: Some(EXIT_AT),
+                ..default()
+            })
+            .insert_resource(ObjectiveTracker::new(objectives))
+            .add_systems(Update, (update_pressure_plate, update_toggle).chain());
+
+        let mut gates = Gates::default();
+        gates.0.insert(
+            "gate_1".to_string(),
+            Gate {
+                status: GateState::Close,
+                key: None,
+                at: EXIT_AT,
+            },
+        );
+        app.insert_resource(gates);
+
+        let gate = app
+            .world_mut()
+            .spawn((Interactable::Gate, GateId("gate_1".to_string())))
+            .id();
+
+        (app, gate)
+    }
+
+    fn gate_state(app: &App) -> GateState {
+        app.world().resource::<Gates>().0["gate_1"].status
+    }
+
+    fn pull(app: &mut App, lever: Entity) {
+        app.world_mut().send_event(ToggleEvent(Toggle {
+            position: Vec2::ZERO,
+            by: CharacterKind::Human,
+            target: lever,
+        }));
+        app.update();
+    }
+
+    #[test]
+    fn test_lever_opens_linked_gate() {
+        let (mut app, gate) = setup(&[Objective::ReachExit]);
+        let lever = app
+            .world_mut()
+            .spawn((Interactable::Lever, InteractionTarget(gate)))
+            .id();
+
+        pull(&mut app, lever);
+
+        assert_eq!(gate_state(&app), GateState::Open);
+        assert!(app.world().get::<Switched>(lever).is_some());
+    }
+
+    #[test]
+    fn test_exit_stays_locked_before_objectives() {
+        let (mut app, gate) = setup(&[Objective::OpenChests(1), Objective::ReachExit]);
+        let lever = app
+            .world_mut()
+            .spawn((Interactable::Lever, InteractionTarget(gate)))
+            .id();
+
+        pull(&mut app, lever);
+
+        assert_eq!(gate_state(&app), GateState::Close);
+    }
+
+    #[test]
+    fn test_toggle_cycle_stops() {
+        let (mut app, _) = setup(&[Objective::ReachExit]);
+        let a = app.world_mut().spawn(Interactable::Lever).id();
+        let b = app
+            .world_mut()
+            .spawn((Interactable::Lever, InteractionTarget(a)))
+            .id();
+        app.world_mut().entity_mut(a).insert(InteractionTarget(b));
+
+        // Doesn't hang, both end up switched
+        pull(&mut app, a);
+
+        assert!(app.world().get::<Switched>(a).is_some());
+        assert!(app.world().get::<Switched>(b).is_some());
+    }
+
+    #[test]
+    fn test_plate_toggles_once() {
+        let (mut app, gate) = setup(&[Objective::ReachExit]);
+        let xy = get_position_from_map(2, 2, None).translation.xy();
+        let plate = app
+            .world_mut()
+            .spawn((
+                Interactable::PressurePlate,
+                PressurePlate,
+                InteractionTarget(gate),
+                Position { xy },
+            ))
+            .id();
+        app.world_mut()
+            .spawn((Position { xy }, CharacterKind::Human));
+
+        app.update();
+        assert_eq!(gate_state(&app), GateState::Open);
+        assert!(app.world().get::<Switched>(plate).is_some());
+
+        // Still standing on it, no more toggles
+        app.world_mut()
+            .resource_mut::<Events<ToggleEvent>>()
+            .clear();
+        app.update();
+        assert!(app.world().resource::<Events<ToggleEvent>>().is_empty());
+    }
+}
@@ -6,13 +6,12 @@ mod core;
 mod dialogs;
 mod entry;
 mod interactions;
-mod macros;
 mod maps;
 
 #[cfg(target_arch = "wasm32")]
 mod web;
 
-use afterlife::over::game_over_system;
+use afterlife::{replay::replay_plugin, result::result_plugin};
 use bevy::{
     asset::AssetMetaCheck,
     log::LogPlugin,
@@ -20,41 +19,22 @@ use bevy::{
     window::{PresentMode, WindowResolution},
 };
 use bevy_pkv::PkvStore;
-use bevy_spritesheet_animation::prelude::*;
-use bevy_stat_bars::RegisterStatbarSubject;
-use big_brain::{BigBrainPlugin, BigBrainSet};
-use brains::{
-    behavior::Behavior,
-    fight::{fight_action_system, fight_scorer_system, fight_system},
-    loot::{loot_action_system, loot_scorer_system, loot_system},
-    thinker::*,
-};
-use characters::{bar::Health, builder::init_character, update::update_character};
+use brains::behavior::Behavior;
 use core::{
-    chest::{update_chest, Chest, Chests},
-    gate::{update_gate, Gates},
-    grave::Grave,
-    layer::{y_sort, SpriteLayer},
-    menu::button_system,
-    point::Exit,
-    scene::{ChunkMap, MainPath},
-    setup::setup_scene,
-    stage::{init_stage, GameStage, Human, Monster},
-    state::GameState,
+    seed::StageSeed,
+    state::{GameState, LoadError},
 };
-use dialogs::ask::{update_ask_dialog, AskDialogEvent};
-use entry::{game, menu, splash, DisplayQuality, Volume};
-use extol_sprite_layer::SpriteLayerPlugin;
-use interactions::{
-    damage::{
-        despawn_damage_indicator, spawn_damage_indicator, update_damage, DamageEvent, Damages,
-    },
-    toggle::{update_toggle_chest, ToggleEvent},
+use entry::{
+    game, menu, settings, splash, DisplayQuality, GameSpeed, KeyBindings, Language, MusicVolume,
+    SfxVolume, Volume,
 };
 
 #[cfg(target_arch = "wasm32")]
 use web::local_storage::get_local_storage_value;
 
+// Regenerate the stage from another public key, see `set_public_key` in main.rs.
+pub use crate::core::seed::set_public_key;
+
 #[derive(Reflect, Resource, Default)]
 #[reflect(Resource)]
 struct Configuration {
@@ -86,14 +66,35 @@ pub fn entry() {
                     ..default()
                 }),
         )
+        // Saves and settings, local storage on web, a file on native
+        .insert_resource(PkvStore::new("TheRustOfUs", "the_rust_of_us"))
         // Insert as resource the initial value for the settings resources
         .insert_resource(DisplayQuality::Medium)
         .insert_resource(Volume(7))
+        .insert_resource(MusicVolume(6))
+        .insert_resource(SfxVolume(9))
+        .init_resource::<Language>()
+        .init_resource::<GameSpeed>()
+        .init_resource::<KeyBindings>()
+        .init_resource::<LoadError>()
+        // Map from the public key of the page, the command line or the default one
+        .insert_resource(
+            get_public_key()
+                .and_then(|public_key| StageSeed::new(&public_key))
+                .unwrap_or_default(),
+        )
         // Declare the game state, whose starting value is determined by the `Default` trait
         .init_state::<GameState>()
         .add_systems(Startup, setup)
         // Adds the plugins for each state
-        .add_plugins((splash::splash_plugin, menu::menu_plugin, game::game_plugin))
+        .add_plugins((
+            splash::splash_plugin,
+            settings::settings_plugin,
+            menu::menu_plugin,
+            game::game_plugin,
+            result_plugin,
+            replay_plugin,
+        ))
         .run();
 }
 
@@ -101,9 +102,31 @@ fn setup(mut commands: Commands) {
     commands.spawn(Camera2dBundle::default());
 }
 
+// Last one set by the page, "GEN STAGE" in index.html.
 #[cfg(target_arch = "wasm32")]
 pub fn get_public_key() -> Option<String> {
     let public_key = get_local_storage_value("public_key");
     debug!("public_key: {:?}", public_key);
     public_key
 }
+
+// `cargo run -- --public-key <public_key>` or `PUBLIC_KEY=<public_key> cargo run`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_public_key() -> Option<String> {
+    get_flag_value(std::env::args(), "--public-key").or_else(|| std::env::var("PUBLIC_KEY").ok())
+}
+
+// Value after `flag`, e.g. `--public-key abc` or `--public-key=abc`.
+#[cfg(not(target_arch = "wasm32"))]
+fn get_flag_value(mut args: impl Iterator<Item = String>, flag: &str) -> Option<String> {
+    let prefix = format!("{flag}=");
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Some(value.to_owned());
+        }
+    }
+    None
+}
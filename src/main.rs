@@ -18,11 +18,12 @@ pub fn wasm_ffi() {
     web_sys::console::log_1(&"Hello from WASM!".into());
 }
 
-// #[wasm_bindgen]
-// pub fn set_public_key(public_key: &str) {
-//     web_sys::console::log_1(&format!("public_key: {:?}", public_key).into());
-//     debug!("public_key: {:?}", public_key);
-// }
+// Called by the page, e.g. "GEN STAGE" in index.html, to regenerate the stage.
+#[wasm_bindgen]
+pub fn set_public_key(public_key: &str) {
+    web_sys::console::log_1(&format!("public_key: {:?}", public_key).into());
+    the_rust_of_us::set_public_key(public_key);
+}
 
 #[cfg(target_arch = "wasm32")]
 fn start_app() {
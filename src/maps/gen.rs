@@ -3,7 +3,7 @@ use crate::core::{
     scene::GameMap,
 };
 use anyhow::Result;
-use rand::{rngs::OsRng, Rng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 fn always_find_path(start: &MapPosition, goal: &MapPosition) -> PathCost {
     find_path(
@@ -21,7 +21,7 @@ fn check_and_pave_path(
     start: &MapPosition,
     main_route_path: &[(usize, usize)],
     target_char: &str,
-    rng: &mut rand::rngs::ThreadRng,
+    rng: &mut StdRng,
 ) {
     for row in 0..8 {
         for col in 0..8 {
@@ -55,8 +55,9 @@ pub fn refine_walkable_map(
     game_map: &mut GameMap,
     start: &MapPosition,
     goal: &MapPosition,
+    seed: u64,
 ) -> (GameMap, Vec<Vec<bool>>) {
-    let mut rng = rand::thread_rng();
+    let mut rng = StdRng::seed_from_u64(seed);
     let GameMap(map) = game_map;
 
     // Check if any node in the main route can walk from start to goal
@@ -90,6 +91,7 @@ pub fn refine_walkable_map(
 #[allow(unused)]
 pub fn gen_map_from_public_key(
     public_key: &str,
+    seed: u64,
 ) -> Result<(
     Vec<Vec<bool>>,
     MapPosition,
@@ -131,7 +133,7 @@ pub fn gen_map_from_public_key(
 
     // Place 🆒 and 🆕
     let mut graves = vec![];
-    let mut rng = OsRng;
+    let mut rng = StdRng::seed_from_u64(seed);
     loop {
         // Place 💰 and 💀 randomly ensuring no conflict with 🆒 and 🆕
         for _ in 0..1 {
@@ -161,7 +163,7 @@ pub fn gen_map_from_public_key(
 #[test]
 fn test_refine_walkable_map() {
     let pubkey = "gistmeAhMG7AcKSPCHis8JikGmKT9tRRyZpyMLNNULq";
-    let (walkables, start, goal, game_map, _) = gen_map_from_public_key(pubkey).unwrap();
+    let (walkables, start, goal, game_map, _) = gen_map_from_public_key(pubkey, 0).unwrap();
     let GameMap(map) = game_map;
 
     #[allow(clippy::needless_range_loop)]
@@ -176,7 +178,7 @@ fn test_refine_walkable_map() {
     let mut walkables = walkables;
 
     let (refined_game_map, refined_walkables) =
-        refine_walkable_map(&mut walkables, &mut GameMap(map), &start, &goal);
+        refine_walkable_map(&mut walkables, &mut GameMap(map), &start, &goal, 0);
 
     let GameMap(map) = refined_game_map;
 
@@ -220,3 +222,12 @@ fn test_refine_walkable_map() {
         }
     }
 }
+
+#[test]
+fn test_gen_map_same_seed() {
+    let pubkey = "gistmeAhMG7AcKSPCHis8JikGmKT9tRRyZpyMLNNULq";
+    let (_, _, _, GameMap(map_a), _) = gen_map_from_public_key(pubkey, 42).unwrap();
+    let (_, _, _, GameMap(map_b), _) = gen_map_from_public_key(pubkey, 42).unwrap();
+
+    assert_eq!(map_a, map_b);
+}